@@ -0,0 +1,276 @@
+// attack and pin queries, plus precomputed attack tables for the leaping
+// pieces and ray tables between squares
+
+use crate::magic::{bishop_attacks, rook_attacks};
+use crate::{Bitboard, Board, Color, Square};
+
+const fn leaper_table(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+
+    let mut square = 0;
+    while square < 64 {
+        let (x, y) = ((square % 8) as i8, (square / 8) as i8);
+
+        let mut i = 0;
+        while i < offsets.len() {
+            let (tx, ty) = (x + offsets[i].0, y + offsets[i].1);
+            if tx >= 0 && tx < 8 && ty >= 0 && ty < 8 {
+                table[square] |= 1 << (tx + ty * 8);
+            }
+            i += 1;
+        }
+
+        square += 1;
+    }
+
+    table
+}
+
+/// Squares a knight attacks from each square (`x + y * 8`).
+pub static KNIGHT_ATTACKS: [u64; 64] = leaper_table(&[
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+]);
+
+/// Squares a king attacks from each square (`x + y * 8`).
+pub static KING_ATTACKS: [u64; 64] = leaper_table(&[
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+]);
+
+/// Squares a pawn attacks from each square (`x + y * 8`), indexed by
+/// `Color as usize`. White pawns capture towards the eighth rank.
+pub static PAWN_ATTACKS: [[u64; 64]; 2] = [
+    leaper_table(&[(-1, 1), (1, 1)]),
+    leaper_table(&[(-1, -1), (1, -1)]),
+];
+
+/// The union of a leaper `table` over every square in `bb`.
+#[inline]
+pub fn leaper_attacks(bb: u64, table: &[u64; 64]) -> u64 {
+    Bitboard(bb)
+        .iter()
+        .fold(0, |all, square| all | table[square.index() as usize])
+}
+
+const fn ray_tables() -> ([[u64; 64]; 64], [[u64; 64]; 64]) {
+    let mut between = [[0; 64]; 64];
+    let mut line = [[0; 64]; 64];
+
+    let directions: [(i8, i8); 8] = [
+        (1, 0),
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ];
+
+    let mut from = 0;
+    while from < 64 {
+        let (fx, fy) = ((from % 8) as i8, (from / 8) as i8);
+
+        let mut d = 0;
+        while d < 8 {
+            let (dx, dy) = directions[d];
+
+            // the whole line through `from` in this direction, both ways
+            let mut full = 1u64 << from;
+            let (mut x, mut y) = (fx + dx, fy + dy);
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                full |= 1 << (x + y * 8);
+                x += dx;
+                y += dy;
+            }
+            let (mut x, mut y) = (fx - dx, fy - dy);
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                full |= 1 << (x + y * 8);
+                x -= dx;
+                y -= dy;
+            }
+
+            let mut squares = 0u64;
+            let (mut x, mut y) = (fx + dx, fy + dy);
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                let to = (x + y * 8) as usize;
+                between[from][to] = squares;
+                line[from][to] = full;
+
+                squares |= 1 << to;
+                x += dx;
+                y += dy;
+            }
+
+            d += 1;
+        }
+
+        from += 1;
+    }
+
+    (between, line)
+}
+
+static RAYS: ([[u64; 64]; 64], [[u64; 64]; 64]) = ray_tables();
+
+/// The squares strictly between `a` and `b`, if they share a rank, file or
+/// diagonal. Otherwise empty.
+pub fn between(a: Square, b: Square) -> u64 {
+    RAYS.0[a.index() as usize][b.index() as usize]
+}
+
+/// The entire rank, file or diagonal through both `a` and `b`, edge to edge.
+/// Empty if they don't share one.
+pub fn line(a: Square, b: Square) -> u64 {
+    RAYS.1[a.index() as usize][b.index() as usize]
+}
+
+impl Board {
+    // every piece of either colour attacking `square` given the occupancy
+    // `occupied`, so that sliders behind a piece that was already traded off
+    // (x-rays) show up once `occupied` no longer contains it
+    pub(crate) fn attackers(&self, square: Square, occupied: u64) -> u64 {
+        let index = square.index() as usize;
+        let diagonal = self.bishops | self.queens;
+        let straight = self.rooks | self.queens;
+
+        // a white pawn attacks `square` from wherever a black pawn on it would
+        // attack, and the other way around
+        (PAWN_ATTACKS[Color::Black as usize][index] & self.pawns & self.white)
+            | (PAWN_ATTACKS[Color::White as usize][index] & self.pawns & self.black)
+            | (KNIGHT_ATTACKS[index] & self.knights)
+            | (KING_ATTACKS[index] & self.kings)
+            | (bishop_attacks(square, occupied) & diagonal)
+            | (rook_attacks(square, occupied) & straight)
+    }
+
+    /// Every piece of `color` that attacks `square`.
+    pub fn attackers_to(&self, square: Square, color: Color) -> u64 {
+        self.attackers(square, self.white | self.black) & self.side(color)
+    }
+
+    pub(crate) fn king_square(&self, color: Color) -> Option<Square> {
+        let king = self.kings & self.side(color);
+        (king != 0).then(|| Square::from_bit(king))
+    }
+
+    /// The pieces giving check to the side to move.
+    pub fn checkers(&self) -> u64 {
+        let color = self.side_to_move();
+        match self.king_square(color) {
+            Some(king) => self.attackers_to(king, !color),
+            None => 0,
+        }
+    }
+
+    /// The pieces of `color` that can't leave the line between their king and
+    /// an enemy slider without exposing the king.
+    pub fn pinned(&self, color: Color) -> u64 {
+        let Some(king) = self.king_square(color) else {
+            return 0;
+        };
+
+        let enemy = self.side(!color);
+        let snipers = ((bishop_attacks(king, 0) & (self.bishops | self.queens))
+            | (rook_attacks(king, 0) & (self.rooks | self.queens)))
+            & enemy;
+
+        let occupied = self.white | self.black;
+        let mut pinned = 0;
+
+        for sniper in Bitboard(snipers) {
+            let blockers = between(king, sniper) & occupied;
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & self.side(color);
+            }
+        }
+
+        pinned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::*;
+    use crate::{king_moves, knight_moves, pawns_capture_down, pawns_capture_up, set_bit};
+
+    #[test]
+    fn test_leaper_tables() {
+        for square in Square::all() {
+            let bit = square.bit();
+            let i = square.index() as usize;
+
+            assert_eq!(KNIGHT_ATTACKS[i], knight_moves(bit, 0));
+            assert_eq!(KING_ATTACKS[i], king_moves(bit, 0));
+            assert_eq!(
+                PAWN_ATTACKS[Color::White as usize][i],
+                pawns_capture_down(bit, 0)
+            );
+            assert_eq!(
+                PAWN_ATTACKS[Color::Black as usize][i],
+                pawns_capture_up(bit, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rays() {
+        // a1 to h8
+        assert_eq!(between(Square::A1, Square::H8), 0x0040201008040200);
+        assert_eq!(line(Square::A1, Square::D4), 0x8040201008040201);
+        assert_eq!(between(Square::A1, Square::A2), 0);
+        assert_eq!(line(Square::E1, Square::E8), 0x1010101010101010);
+
+        // not on a shared line
+        assert_eq!(between(Square::A1, Square::B3), 0);
+        assert_eq!(line(Square::A1, Square::B3), 0);
+
+        assert_eq!(
+            between(Square::F3, Square::B7),
+            between(Square::B7, Square::F3)
+        );
+    }
+
+    #[test]
+    fn test_checkers() {
+        // knight and rook both check the black king
+        let board = Board::from("4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1");
+        assert_eq!(board.checkers(), set_bit(set_bit(0, 3, 5), 4, 0));
+        assert!(board.in_check());
+
+        assert_eq!(Board::default().checkers(), 0);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let board = Board::default();
+        // f3 is covered by the e2 and g2 pawns and the g1 knight
+        assert_eq!(
+            board.attackers_to(Square::F3, Color::White),
+            set_bit(set_bit(set_bit(0, 4, 1), 6, 1), 6, 0)
+        );
+        assert_eq!(board.attackers_to(Square::F3, Color::Black), 0);
+    }
+
+    #[test]
+    fn test_pinned() {
+        // the d2 knight is pinned by the bishop, the e2 rook by the rook, and
+        // the f2 pawn is shielded by the g3 pawn in front of it
+        let board = Board::from("4r2k/8/8/b7/7b/6P1/3NRP2/4K3 w - - 0 1");
+        assert_eq!(board.pinned(Color::White), set_bit(set_bit(0, 3, 1), 4, 1));
+        assert_eq!(board.pinned(Color::Black), 0);
+    }
+}
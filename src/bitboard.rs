@@ -0,0 +1,292 @@
+// a set of squares, one bit per square (`x + y * 8`), with the set operations
+// and shifts the free `bitboard_*` functions provide for bare u64s
+
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+use crate::{
+    bitboard_flip_horizontal, bitboard_flip_vertical, bitboard_rotate_180, bitboard_shd,
+    bitboard_shdl, bitboard_shdr, bitboard_shl, bitboard_shr, bitboard_shu, bitboard_shul,
+    bitboard_shur, Square,
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(!0);
+
+    pub const fn from_square(square: Square) -> Bitboard {
+        Bitboard(square.bit())
+    }
+
+    pub const fn contains(self, square: Square) -> bool {
+        self.0 & square.bit() != 0
+    }
+
+    pub const fn popcount(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn with(self, square: Square) -> Bitboard {
+        Bitboard(self.0 | square.bit())
+    }
+
+    pub fn without(self, square: Square) -> Bitboard {
+        Bitboard(self.0 & !square.bit())
+    }
+
+    /// The squares in the set, a1 first.
+    pub fn iter(self) -> Squares {
+        Squares(self.0)
+    }
+
+    // the shifts follow `bitboard_sh*`: left is towards the a-file and up is
+    // towards rank 1, the top row when printed. squares shifted off the board
+    // are dropped rather than wrapping around
+
+    pub fn shift_left(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shl(self.0, i))
+    }
+
+    pub fn shift_right(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shr(self.0, i))
+    }
+
+    pub fn shift_up(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shu(self.0, i))
+    }
+
+    pub fn shift_down(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shd(self.0, i))
+    }
+
+    pub fn shift_up_left(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shul(self.0, i))
+    }
+
+    pub fn shift_down_left(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shdl(self.0, i))
+    }
+
+    pub fn shift_up_right(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shur(self.0, i))
+    }
+
+    pub fn shift_down_right(self, i: u8) -> Bitboard {
+        Bitboard(bitboard_shdr(self.0, i))
+    }
+
+    /// Mirrors the ranks, so a1 becomes a8.
+    pub fn flip_vertical(self) -> Bitboard {
+        Bitboard(bitboard_flip_vertical(self.0))
+    }
+
+    /// Mirrors the files, so a1 becomes h1.
+    pub fn flip_horizontal(self) -> Bitboard {
+        Bitboard(bitboard_flip_horizontal(self.0))
+    }
+
+    /// Turns the board around, so a1 becomes h8.
+    pub fn rotate_180(self) -> Bitboard {
+        Bitboard(bitboard_rotate_180(self.0))
+    }
+}
+
+/// Pops squares off a bitboard, lowest first.
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    #[inline]
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let square = Square::from_bit(self.0);
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Squares {
+        self.iter()
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        Bitboard(iter.into_iter().fold(0, |bb, square| bb | square.bit()))
+    }
+}
+
+impl From<u64> for Bitboard {
+    fn from(bb: u64) -> Self {
+        Bitboard(bb)
+    }
+}
+
+impl From<Bitboard> for u64 {
+    fn from(bb: Bitboard) -> Self {
+        bb.0
+    }
+}
+
+impl From<Square> for Bitboard {
+    fn from(square: Square) -> Self {
+        Bitboard::from_square(square)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Bitboard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
+
+// rank 1 at the top, like `print_board`
+impl fmt::Display for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..8 {
+            for x in 0..8 {
+                let bit = self.0 >> (x + y * 8) & 1;
+                write!(f, "{bit}{}", if x == 7 { "\n" } else { " " })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bitboard(0x{:016x})", self.0)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitboard::*;
+
+    #[test]
+    fn test_set_operations() {
+        let a = Bitboard::from_square(Square::E4).with(Square::D5);
+        let b = Bitboard::from(Square::D5);
+
+        assert_eq!(a & b, b);
+        assert_eq!((a | b).popcount(), 2);
+        assert_eq!(a & !b, Bitboard::from(Square::E4));
+        assert!(a.contains(Square::E4));
+        assert!(!a.without(Square::E4).contains(Square::E4));
+        assert!((!Bitboard::FULL).is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let bb = Bitboard(0x8000_0000_0000_0101);
+        assert_eq!(
+            bb.iter().collect::<Vec<_>>(),
+            vec![Square::A1, Square::A2, Square::H8]
+        );
+        assert_eq!(bb.iter().len(), 3);
+        assert_eq!(bb.iter().collect::<Bitboard>(), bb);
+        assert_eq!(Bitboard::EMPTY.iter().next(), None);
+    }
+
+    #[test]
+    fn test_shifts() {
+        let e4 = Bitboard::from(Square::E4);
+        assert_eq!(e4.shift_down(1), Bitboard::from(Square::E5));
+        assert_eq!(e4.shift_up(1), Bitboard::from(Square::E3));
+        assert_eq!(e4.shift_left(1), Bitboard::from(Square::D4));
+        assert_eq!(e4.shift_right(1), Bitboard::from(Square::F4));
+        assert_eq!(e4.shift_down_right(1), Bitboard::from(Square::F5));
+        assert_eq!(e4.shift_up_left(1), Bitboard::from(Square::D3));
+
+        // nothing wraps around the edge of the board
+        assert!(Bitboard::from(Square::A1).shift_left(1).is_empty());
+        assert!(Bitboard::from(Square::H1).shift_right(2).is_empty());
+
+        // or off it, however far the shift
+        let full = Bitboard::FULL;
+        for i in [8, 9, 32, 40, 255] {
+            assert!(full.shift_up(i).is_empty());
+            assert!(full.shift_down(i).is_empty());
+            assert!(full.shift_up_left(i).is_empty());
+            assert!(full.shift_up_right(i).is_empty());
+            assert!(full.shift_down_left(i).is_empty());
+            assert!(full.shift_down_right(i).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_flips() {
+        let bb = Bitboard::from(Square::B1) | Bitboard::from(Square::E4);
+        let flipped = |squares: [Square; 2]| squares.into_iter().collect::<Bitboard>();
+        assert_eq!(bb.flip_vertical(), flipped([Square::B8, Square::E5]));
+        assert_eq!(bb.flip_horizontal(), flipped([Square::G1, Square::D4]));
+        assert_eq!(bb.rotate_180(), flipped([Square::G8, Square::D5]));
+        assert_eq!(bb.flip_vertical().flip_horizontal(), bb.rotate_180());
+    }
+
+    #[test]
+    fn test_display() {
+        let bb = Bitboard::from(Square::A1) | Bitboard::from(Square::H8);
+        let grid = bb.to_string();
+        let rows: Vec<_> = grid.lines().collect();
+
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[0], "1 0 0 0 0 0 0 0");
+        assert_eq!(rows[7], "0 0 0 0 0 0 0 1");
+        assert!(format!("{bb:?}").starts_with("Bitboard(0x8000000000000001)\n"));
+    }
+}
@@ -0,0 +1,161 @@
+// step-by-step position setup that is checked before it reaches the engine
+
+use crate::{Board, CastlingRights, Color, File, Piece, PositionError, Square, Variant};
+
+/// Builds a [`Board`] from an empty one: no pieces, no castling rights, white
+/// to move. `build` refuses positions that fail [`Board::validate`].
+#[derive(Clone, Copy)]
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoardBuilder {
+    pub fn new() -> Self {
+        let mut board = Board::new();
+        board.castling = CastlingRights::NONE;
+
+        Self { board }
+    }
+
+    /// Puts `piece` on `square`, replacing whatever was there.
+    pub fn piece(mut self, square: Square, piece: Piece, color: Color) -> Self {
+        self.board.set(piece, color, square);
+        self
+    }
+
+    /// The variant whose rules `build` checks the position against.
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.board.variant = variant;
+        self
+    }
+
+    pub fn side_to_move(mut self, color: Color) -> Self {
+        self.board.whites_turn = color == Color::White;
+        self
+    }
+
+    /// Castling rights with the rooks on the h and a files.
+    pub fn castling(mut self, color: Color, kingside: bool, queenside: bool) -> Self {
+        let castling = &mut self.board.castling;
+        castling.set(color, true, kingside.then_some(File::H));
+        castling.set(color, false, queenside.then_some(File::A));
+        self
+    }
+
+    /// A castling right with the rook on `file`, for chess960.
+    pub fn castling_rook(mut self, color: Color, kingside: bool, file: File) -> Self {
+        self.board.castling.set(color, kingside, Some(file));
+        self
+    }
+
+    /// The square a pawn skipped over with a double push last move.
+    pub fn en_passant(mut self, square: Square) -> Self {
+        self.board.en_passant_targets = square.bit();
+        self
+    }
+
+    pub fn clocks(mut self, halfmove_clock: u32, fullmove_number: u32) -> Self {
+        self.board.halfmove_clock = halfmove_clock;
+        self.board.fullmove_number = fullmove_number;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Board, Vec<PositionError>> {
+        self.board.chess960 = self.board.needs_chess960();
+        self.board.validate().map(|_| self.board)
+    }
+}
+
+impl Board {
+    pub fn builder() -> BoardBuilder {
+        BoardBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::*;
+    use crate::Move;
+
+    #[test]
+    fn test_matches_fen() {
+        let board = Board::builder()
+            .piece(Square::E1, Piece::King, Color::White)
+            .piece(Square::E5, Piece::Pawn, Color::White)
+            .piece(Square::E8, Piece::King, Color::Black)
+            .piece(Square::A8, Piece::Rook, Color::Black)
+            .piece(Square::D5, Piece::Pawn, Color::Black)
+            .castling(Color::Black, false, true)
+            .en_passant(Square::D6)
+            .clocks(0, 40)
+            .build()
+            .unwrap();
+
+        assert!(board == Board::from("r3k3/8/8/3pP3/8/8/8/4K3 w q d6 0 40"));
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        let errors = Board::builder()
+            .piece(Square::E1, Piece::King, Color::White)
+            .piece(Square::E8, Piece::King, Color::Black)
+            .piece(Square::E2, Piece::Rook, Color::Black)
+            .side_to_move(Color::Black)
+            .castling(Color::White, true, true)
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(errors.contains(&PositionError::OpponentInCheck));
+        assert!(errors.contains(&PositionError::CastlingRights {
+            color: Color::White,
+            kingside: true
+        }));
+    }
+
+    #[test]
+    fn test_variant() {
+        // no white king is an error in standard chess but not in horde
+        let builder = Board::builder()
+            .piece(Square::A1, Piece::Pawn, Color::White)
+            .piece(Square::E8, Piece::King, Color::Black);
+        assert_eq!(
+            builder.build().err(),
+            Some(vec![
+                PositionError::KingCount(Color::White, 0),
+                PositionError::PawnOnBackRank(Square::A1),
+            ])
+        );
+
+        let board = builder.variant(Variant::Horde).build().unwrap();
+        assert_eq!(board.variant, Variant::Horde);
+        assert!(
+            board
+                == Board::from_variant_fen(Variant::Horde, "4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_clocks() {
+        let board = Board::builder()
+            .piece(Square::E1, Piece::King, Color::White)
+            .piece(Square::E8, Piece::King, Color::Black)
+            .piece(Square::A2, Piece::Pawn, Color::White)
+            .side_to_move(Color::Black)
+            .clocks(7, 30)
+            .build()
+            .unwrap();
+
+        let board = board.play(Move::new(Square::E8, Square::D8, None)).unwrap();
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (8, 31));
+
+        let board = board.play(Move::new(Square::A2, Square::A4, None)).unwrap();
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (0, 31));
+    }
+}
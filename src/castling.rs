@@ -0,0 +1,341 @@
+// castling rights and moves, for standard chess and chess960.
+//
+// a castling move is stored as the king taking its own rook, which is the only
+// unambiguous way to write it when the king starts next to its destination.
+// standard chess still accepts the king moving two squares.
+
+use crate::attacks::between;
+use crate::{Bitboard, Board, Color, File, Piece, Rank, Square};
+
+/// The file of each rook that may still castle, indexed by colour and then
+/// kingside (0) or queenside (1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights([[Option<File>; 2]; 2]);
+
+impl CastlingRights {
+    pub const NONE: CastlingRights = CastlingRights([[None; 2]; 2]);
+    pub const STANDARD: CastlingRights = CastlingRights([[Some(File::H), Some(File::A)]; 2]);
+
+    fn side(kingside: bool) -> usize {
+        if kingside {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// The file of the rook `color` may castle with on that side.
+    pub fn rook(&self, color: Color, kingside: bool) -> Option<File> {
+        self.0[color as usize][Self::side(kingside)]
+    }
+
+    pub fn set(&mut self, color: Color, kingside: bool, rook: Option<File>) {
+        self.0[color as usize][Self::side(kingside)] = rook;
+    }
+
+    /// Whether `color` may still castle (kingside, queenside).
+    pub fn can_castle(&self, color: Color) -> (bool, bool) {
+        (
+            self.rook(color, true).is_some(),
+            self.rook(color, false).is_some(),
+        )
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.0[color as usize] = [None; 2];
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    // a rook moving from, or being captured on, its starting square loses its
+    // right to castle
+    pub(crate) fn touch(&mut self, square: Square) {
+        for color in [Color::White, Color::Black] {
+            if square.rank() != home_rank(color) {
+                continue;
+            }
+
+            for rook in &mut self.0[color as usize] {
+                if *rook == Some(square.file()) {
+                    *rook = None;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn home_rank(color: Color) -> Rank {
+    match color {
+        Color::White => Rank::R1,
+        Color::Black => Rank::R8,
+    }
+}
+
+/// Where the king and the rook end up after castling.
+pub(crate) fn castled_squares(color: Color, kingside: bool) -> (Square, Square) {
+    let rank = home_rank(color);
+    if kingside {
+        (Square::new(File::G, rank), Square::new(File::F, rank))
+    } else {
+        (Square::new(File::C, rank), Square::new(File::D, rank))
+    }
+}
+
+impl Board {
+    fn castling_rook_square(&self, color: Color, kingside: bool) -> Option<Square> {
+        let file = self.castling.rook(color, kingside)?;
+        Some(Square::new(file, home_rank(color)))
+    }
+
+    // the rook squares the king on `king` could castle onto, as far as the
+    // pieces in the way are concerned. attacked squares are left to
+    // `move_piece`
+    pub(crate) fn castling_targets(&self, king: Square, color: Color) -> u64 {
+        if king.rank() != home_rank(color) {
+            return 0;
+        }
+
+        let occupied = self.white | self.black;
+        let mut targets = 0;
+        for kingside in [true, false] {
+            let Some(rook) = self.castling_rook_square(color, kingside) else {
+                continue;
+            };
+            if self.piece_at(rook) != Some((Piece::Rook, color))
+                || (rook.file() > king.file()) != kingside
+            {
+                continue;
+            }
+
+            let (king_to, rook_to) = castled_squares(color, kingside);
+            let path =
+                between(king, king_to) | king_to.bit() | between(rook, rook_to) | rook_to.bit();
+            if path & occupied & !king.bit() & !rook.bit() == 0 {
+                targets |= rook.bit();
+            }
+        }
+
+        targets
+    }
+
+    // turns the king moving two squares, the usual way to write castling in
+    // standard chess, into the king taking its rook
+    pub(crate) fn castling_rook(&self, from: Square, to: Square, color: Color) -> Option<Square> {
+        let home = home_rank(color);
+        if self.chess960 || from != Square::new(File::E, home) || to.rank() != home {
+            return None;
+        }
+
+        match to.file() {
+            File::G => self.castling_rook_square(color, true),
+            File::C => self.castling_rook_square(color, false),
+            _ => None,
+        }
+    }
+
+    /// Whether the king on `king` may castle with the rook on `rook` without
+    /// starting in, passing through or landing on an attacked square.
+    pub(crate) fn castling_is_safe(&self, king: Square, rook: Square, color: Color) -> bool {
+        let (king_to, _) = castled_squares(color, rook.file() > king.file());
+        let path = king.bit() | between(king, king_to) | king_to.bit();
+        Bitboard(path)
+            .iter()
+            .all(|square| self.attackers_to(square, !color) == 0)
+    }
+
+    // whether the castling rights only make sense in chess960
+    pub(crate) fn needs_chess960(&self) -> bool {
+        [Color::White, Color::Black].into_iter().any(|color| {
+            let (kingside, queenside) = (
+                self.castling.rook(color, true),
+                self.castling.rook(color, false),
+            );
+            let king = self.kings & self.side(color) & Square::new(File::E, home_rank(color)).bit();
+
+            (kingside.is_some() || queenside.is_some()) && king == 0
+                || kingside.is_some_and(|file| file != File::H)
+                || queenside.is_some_and(|file| file != File::A)
+        })
+    }
+
+    /// The chess960 starting position with the given Scharnagl number, from 0
+    /// to 959. Number 518 is the standard starting position.
+    pub fn chess960(index: u16) -> Option<Board> {
+        if index >= 960 {
+            return None;
+        }
+
+        let mut back_rank = [None; 8];
+        let mut n = index as usize;
+
+        back_rank[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+        n /= 4;
+        back_rank[(n % 4) * 2] = Some(Piece::Bishop);
+        n /= 4;
+
+        let mut place = |nth: usize, piece: Piece| {
+            let file = (0..8).filter(|f| back_rank[*f].is_none()).nth(nth).unwrap();
+            back_rank[file] = Some(piece);
+        };
+
+        place(n % 6, Piece::Queen);
+        n /= 6;
+
+        // both knights go among the five squares left, the second one
+        // counted after the first has been placed
+        let (first, second) = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 2),
+            (2, 3),
+            (3, 3),
+        ][n];
+        place(first, Piece::Knight);
+        place(second, Piece::Knight);
+
+        place(0, Piece::Rook);
+        place(0, Piece::King);
+        place(0, Piece::Rook);
+
+        let mut board = Board::new();
+        board.castling = CastlingRights::NONE;
+        board.chess960 = true;
+
+        for (file, piece) in File::all().zip(back_rank) {
+            let piece = piece.unwrap();
+            board.set(piece, Color::White, Square::new(file, Rank::R1));
+            board.set(piece, Color::Black, Square::new(file, Rank::R8));
+            board.set(Piece::Pawn, Color::White, Square::new(file, Rank::R2));
+            board.set(Piece::Pawn, Color::Black, Square::new(file, Rank::R7));
+
+            if piece == Piece::Rook {
+                // the first rook found is on the queenside
+                let kingside = board.castling.rook(Color::White, false).is_some();
+                board.castling.set(Color::White, kingside, Some(file));
+                board.castling.set(Color::Black, kingside, Some(file));
+            }
+        }
+
+        Some(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castling::*;
+    use crate::Move;
+
+    #[test]
+    fn test_chess960_positions() {
+        let standard = Board::chess960(518).unwrap();
+        assert_eq!(
+            standard.fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        assert_eq!(
+            Board::chess960(0).unwrap().fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert_eq!(
+            Board::chess960(959)
+                .unwrap()
+                .shredder_fen()
+                .split(' ')
+                .next(),
+            Some("rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB")
+        );
+        assert!(Board::chess960(960).is_none());
+
+        let mut seen = std::collections::HashSet::new();
+        for index in 0..960 {
+            let board = Board::chess960(index).unwrap();
+            assert_eq!(board.validate(), Ok(()));
+            assert!(!board.legal_moves().is_empty());
+            assert!(seen.insert(board.fen()));
+        }
+    }
+
+    #[test]
+    fn test_chess960_perft() {
+        let board =
+            Board::from("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert_eq!(board.perft(3), 12189);
+
+        let board = Board::from("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9");
+        assert_eq!(board.perft(3), 18002);
+    }
+
+    #[test]
+    fn test_castling_rights() {
+        let mut rights = CastlingRights::STANDARD;
+        rights.touch(Square::H1);
+        assert_eq!(rights.can_castle(Color::White), (false, true));
+        rights.touch(Square::A1);
+        rights.clear(Color::Black);
+        assert!(rights.is_empty());
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // the king on b1 castles queenside with the rook next to it on a1,
+        // ending on c1 with the rook on d1
+        let board = Board::from("4k3/8/8/8/8/8/8/RK5R w HA - 0 1");
+        let castle = Move::new(Square::B1, Square::A1, None);
+        assert!(board.legal_moves().contains(&castle));
+
+        let castled = board.play(castle).unwrap();
+        assert_eq!(castled.fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+
+        // but not while the rook on c8 attacks c1
+        let board = Board::from("2r1k3/8/8/8/8/8/8/RK5R w A - 0 1");
+        assert!(!board.legal_moves().contains(&castle));
+
+        // the king only needs to move one square to g1 on kingside
+        let board = Board::from("4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+        let castled = board.play(Move::new(Square::F1, Square::G1, None)).unwrap();
+        assert_eq!(
+            castled.piece_at(Square::G1),
+            Some((Piece::King, Color::White))
+        );
+        assert_eq!(
+            castled.piece_at(Square::F1),
+            Some((Piece::Rook, Color::White))
+        );
+
+        // without a rook to castle with, f1g1 is just a king move
+        let board = Board::from("4k3/8/8/8/8/8/8/5K2 w - - 0 1");
+        let moved = board.play(Move::new(Square::F1, Square::G1, None)).unwrap();
+        assert_eq!(
+            moved.piece_at(Square::G1),
+            Some((Piece::King, Color::White))
+        );
+    }
+
+    #[test]
+    fn test_standard_castling_notation() {
+        let board = Board::from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let castle = Move::new(Square::E1, Square::H1, None);
+        assert!(board.legal_moves().contains(&castle));
+        assert_eq!(board.uci_move(castle), "e1g1");
+        assert_eq!(board.parse_uci("e1g1"), Ok(castle));
+        assert_eq!(board.parse_uci("e1h1"), Ok(castle));
+
+        let mut board = board;
+        board.uci("e1c1".to_string(), None).unwrap();
+        assert_eq!(board.fen(), "r3k2r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+
+        let chess960 = Board::from("rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1");
+        assert_eq!(
+            chess960.uci_move(Move::new(Square::B1, Square::A1, None)),
+            "b1a1"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+// crazyhouse: a captured piece changes sides and goes into the capturer's
+// pocket, from where it can be dropped back onto any empty square instead of
+// making a move. a promoted piece goes back to being a pawn when it is taken
+
+use crate::{Bitboard, Board, Color, Move, Piece, Rank, Square, Undo, Variant};
+
+/// The pieces a side holds in hand, counted by piece. Kings are never held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Pocket([u8; 5]);
+
+impl Pocket {
+    pub const EMPTY: Pocket = Pocket([0; 5]);
+
+    /// The pieces that can be held, in the order FEN lists them.
+    pub const PIECES: [Piece; 5] = [
+        Piece::Queen,
+        Piece::Rook,
+        Piece::Bishop,
+        Piece::Knight,
+        Piece::Pawn,
+    ];
+
+    pub fn count(&self, piece: Piece) -> u8 {
+        match piece {
+            Piece::King => 0,
+            piece => self.0[piece as usize],
+        }
+    }
+
+    pub fn add(&mut self, piece: Piece) {
+        if piece != Piece::King {
+            self.0[piece as usize] += 1;
+        }
+    }
+
+    /// Takes one `piece` out of the pocket, if there is one.
+    pub fn remove(&mut self, piece: Piece) -> bool {
+        if self.count(piece) == 0 {
+            return false;
+        }
+
+        self.0[piece as usize] -= 1;
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::EMPTY
+    }
+
+    /// Every piece held, once for each copy.
+    pub fn pieces(&self) -> impl Iterator<Item = Piece> + '_ {
+        Self::PIECES
+            .into_iter()
+            .flat_map(|piece| std::iter::repeat_n(piece, self.count(piece) as usize))
+    }
+}
+
+impl Board {
+    pub fn pocket(&self, color: Color) -> &Pocket {
+        &self.pockets[color as usize]
+    }
+
+    pub fn pocket_mut(&mut self, color: Color) -> &mut Pocket {
+        &mut self.pockets[color as usize]
+    }
+
+    /// The pieces on the board that were promoted from pawns.
+    pub fn promoted(&self) -> Bitboard {
+        Bitboard(self.promoted)
+    }
+
+    pub(crate) fn set_promoted(&mut self, square: Square) {
+        self.promoted |= square.bit();
+    }
+
+    // every empty square `piece` may be dropped on: anywhere but the first
+    // and last rank for a pawn
+    pub(crate) fn drop_targets(&self, piece: Piece) -> u64 {
+        let empty = !(self.white | self.black);
+        match piece {
+            Piece::Pawn => empty & !(Rank::R1.bitboard() | Rank::R8.bitboard()),
+            _ => empty,
+        }
+    }
+
+    pub(crate) fn drop_moves(&self, moves: &mut Vec<Move>) {
+        if self.variant != Variant::Crazyhouse {
+            return;
+        }
+
+        let pocket = *self.pocket(self.side_to_move());
+        for piece in Pocket::PIECES {
+            if pocket.count(piece) == 0 {
+                continue;
+            }
+
+            for to in Bitboard(self.drop_targets(piece)) {
+                moves.push(Move::drop(piece, to));
+            }
+        }
+    }
+
+    /// Drops `piece` from the side to move's pocket onto `to`.
+    pub fn drop_piece(&mut self, piece: Piece, to: Square) -> Result<(), String> {
+        self.make_drop(piece, to, &mut Undo::new(self))
+    }
+
+    // `drop_piece`, recording what it changes in `undo`
+    pub(crate) fn make_drop(
+        &mut self,
+        piece: Piece,
+        to: Square,
+        undo: &mut Undo,
+    ) -> Result<(), String> {
+        let color = self.side_to_move();
+
+        if let Some(outcome) = self.variant_outcome() {
+            return Err(format!("the game is over: {outcome:?}"));
+        }
+        if self.drop_targets(piece) & to.bit() == 0 {
+            return Err(format!("cannot drop {piece:?} on {to}"));
+        }
+        if self.pocket(color).count(piece) == 0 {
+            return Err(format!("{color:?} has no {piece:?} to drop"));
+        }
+
+        self.pocket_mut(color).remove(piece);
+        self.set_undoable(undo, piece, color, to);
+        self.whites_turn = !self.whites_turn;
+
+        if !self.king_safe(color) {
+            self.unmake(undo);
+            return Err(format!(
+                "dropping on {to} is not legal (puts {color:?} in check)"
+            ));
+        }
+
+        self.en_passant_targets = 0;
+
+        if piece == Piece::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        Ok(())
+    }
+
+    // what a crazyhouse move does besides moving the piece: the captured
+    // piece goes into the mover's pocket, as a pawn if it had been promoted,
+    // and promoted pieces stay marked as such wherever they go. called before
+    // the pieces move
+    pub(crate) fn update_pockets(
+        &mut self,
+        from: Square,
+        to: Square,
+        captured: Option<Piece>,
+        promotion: bool,
+    ) {
+        let promoted = self.promoted;
+        if let Some(piece) = captured {
+            let piece = if promoted & to.bit() != 0 {
+                Piece::Pawn
+            } else {
+                piece
+            };
+            let color = self.side_to_move();
+            self.pocket_mut(color).add(piece);
+        }
+
+        self.promoted &= !(from.bit() | to.bit());
+        if promotion || promoted & from.bit() != 0 {
+            self.promoted |= to.bit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crazyhouse::*;
+
+    #[test]
+    fn test_pocket() {
+        let mut pocket = Pocket::EMPTY;
+        pocket.add(Piece::Knight);
+        pocket.add(Piece::Pawn);
+        pocket.add(Piece::Knight);
+        assert_eq!(pocket.count(Piece::Knight), 2);
+        assert_eq!(
+            pocket.pieces().collect::<Vec<_>>(),
+            vec![Piece::Knight, Piece::Knight, Piece::Pawn]
+        );
+
+        assert!(pocket.remove(Piece::Pawn));
+        assert!(!pocket.remove(Piece::Pawn));
+        assert!(!pocket.is_empty());
+    }
+
+    #[test]
+    fn test_captures_fill_pockets() {
+        let board = Board::start(Variant::Crazyhouse);
+        let board = ["e2e4", "d7d5", "e4d5", "d8d5"]
+            .into_iter()
+            .fold(board, |board, uci| {
+                board.play(board.parse_uci(uci).unwrap()).unwrap()
+            });
+
+        assert_eq!(board.pocket(Color::White).count(Piece::Pawn), 1);
+        assert_eq!(board.pocket(Color::Black).count(Piece::Pawn), 1);
+        assert_eq!(
+            board.fen(),
+            "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3"
+        );
+
+        let board = board.play(board.parse_uci("P@e4").unwrap()).unwrap();
+        assert_eq!(
+            board.piece_at(Square::E4),
+            Some((Piece::Pawn, Color::White))
+        );
+        assert!(board.pocket(Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_drops() {
+        let board = Board::from("4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1");
+        assert_eq!(board.variant, Variant::Crazyhouse);
+
+        // pawns can't go on the first or last rank
+        assert!(board.play(Move::drop(Piece::Pawn, Square::A8)).is_none());
+        assert!(board.play(Move::drop(Piece::Pawn, Square::E7)).is_some());
+        // and nobody can drop what they don't have
+        assert!(board.play(Move::drop(Piece::Knight, Square::C3)).is_none());
+        assert!(board.play(Move::drop(Piece::Pawn, Square::E1)).is_none());
+
+        // a drop can block a check
+        let board = Board::from("4k3/8/8/8/4r3/8/8/4K3[N] w - - 0 1");
+        let moves = board.legal_moves();
+        assert!(moves.contains(&Move::drop(Piece::Knight, Square::E2)));
+        assert!(!moves.contains(&Move::drop(Piece::Knight, Square::A1)));
+    }
+
+    #[test]
+    fn test_promoted_pieces_demote() {
+        let board = Board::from("4k3/1P6/8/8/8/8/r7/4K3[] w - - 0 1");
+        let board = board.play(board.parse_uci("b7b8q").unwrap()).unwrap();
+        assert_eq!(board.fen(), "1Q~2k3/8/8/8/8/8/r7/4K3[] b - - 0 1");
+
+        // the queen keeps its mark when it moves, so taking it only gets
+        // black a pawn
+        let board = board.play(board.parse_uci("e8d7").unwrap()).unwrap();
+        let board = board.play(board.parse_uci("b8b2").unwrap()).unwrap();
+        assert_eq!(board.promoted(), Bitboard::from(Square::B2));
+        let board = board.play(board.parse_uci("a2b2").unwrap()).unwrap();
+        assert_eq!(board.pocket(Color::Black).count(Piece::Pawn), 1);
+        assert_eq!(board.pocket(Color::Black).count(Piece::Queen), 0);
+        assert!(board.promoted().is_empty());
+    }
+
+    #[test]
+    fn test_perft() {
+        assert_eq!(Board::start(Variant::Crazyhouse).perft(3), 8902);
+
+        // every kind of drop: 62 squares for each piece, 48 for the pawn and
+        // five king moves
+        let board = Board::from("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1");
+        assert_eq!(board.perft(1), 301);
+        assert_eq!(board.perft(2), 75353);
+
+        // the queen on b7 only gives black a pawn when taken
+        let board = Board::from("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1");
+        assert_eq!(board.perft(2), 360);
+    }
+}
@@ -0,0 +1,393 @@
+// endgames whose outcome is known, picked by the material on the board.
+//
+// a won endgame scores above any amount of material, plus a little for
+// driving the lone king to the edge (or the right corner) and bringing the
+// other king close, so the search keeps making progress toward the mate.
+// drawn ones score zero however much material is left.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::eval::{piece_value, PAWN_VALUE};
+use crate::{kpk, Bitboard, Board, Color, File, Piece, Rank, Square};
+
+/// Added to the score of an endgame that is won. Well below the mate scores
+/// of the search.
+pub const KNOWN_WIN: i32 = 10_000;
+
+/// How many of each piece both sides have, indexed by colour and piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Material([[u8; 6]; 2]);
+
+impl Material {
+    /// The material of `strong` and `weak` pieces besides the kings, with
+    /// the strong side as white.
+    const fn new(strong: &[Piece], weak: &[Piece]) -> Material {
+        let mut counts = [LONE_KING; 2];
+
+        let mut i = 0;
+        while i < strong.len() {
+            counts[0][strong[i] as usize] += 1;
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < weak.len() {
+            counts[1][weak[i] as usize] += 1;
+            i += 1;
+        }
+
+        Material(counts)
+    }
+
+    pub fn of(board: &Board) -> Material {
+        let mut counts = [[0; 6]; 2];
+        for color in [Color::White, Color::Black] {
+            for piece in Piece::ALL {
+                let pieces = board.pieces(piece) & board.side(color);
+                counts[color as usize][piece as usize] = pieces.count_ones() as u8;
+            }
+        }
+
+        Material(counts)
+    }
+
+    pub fn count(&self, color: Color, piece: Piece) -> u8 {
+        self.0[color as usize][piece as usize]
+    }
+
+    /// The same material with the colours swapped.
+    pub fn flipped(&self) -> Material {
+        Material([self.0[1], self.0[0]])
+    }
+}
+
+// pieces in the order material is written
+const ORDER: [(Piece, char); 6] = [
+    (Piece::King, 'K'),
+    (Piece::Queen, 'Q'),
+    (Piece::Rook, 'R'),
+    (Piece::Bishop, 'B'),
+    (Piece::Knight, 'N'),
+    (Piece::Pawn, 'P'),
+];
+
+impl fmt::Display for Material {
+    /// White's pieces, `v` and black's, such as `KBNvK`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for color in [Color::White, Color::Black] {
+            if color == Color::Black {
+                write!(f, "v")?;
+            }
+            for (piece, c) in ORDER {
+                for _ in 0..self.count(color, piece) {
+                    write!(f, "{c}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Material {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (white, black) = s
+            .split_once('v')
+            .ok_or(format!("{s} doesn't have a 'v' between the sides"))?;
+
+        let mut counts = [[0; 6]; 2];
+        for (color, side) in [white, black].into_iter().enumerate() {
+            for c in side.chars() {
+                let (piece, _) = ORDER
+                    .iter()
+                    .find(|(_, name)| *name == c.to_ascii_uppercase())
+                    .ok_or(format!("{c} is not a piece"))?;
+                counts[color][*piece as usize] += 1;
+            }
+        }
+
+        Ok(Material(counts))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endgame {
+    /// King and pawn against king, decided by the bitbase.
+    Kpk,
+    /// King and rook or queen against king.
+    Krk,
+    Kqk,
+    /// King, bishop and knight against king: mate is only possible in a
+    /// corner the bishop can reach.
+    Kbnk,
+    /// A single minor piece (or nothing) can't mate.
+    InsufficientMaterial,
+    /// King, bishop and pawns on one rook file against king. If the bishop
+    /// can't cover the promotion square, the lone king holds the corner.
+    WrongBishop,
+}
+
+const LONE_KING: [u8; 6] = [0, 0, 0, 0, 0, 1];
+
+const ENDGAMES: [(Material, Endgame); 7] = [
+    (Material::new(&[Piece::Pawn], &[]), Endgame::Kpk),
+    (Material::new(&[Piece::Rook], &[]), Endgame::Krk),
+    (Material::new(&[Piece::Queen], &[]), Endgame::Kqk),
+    (
+        Material::new(&[Piece::Bishop, Piece::Knight], &[]),
+        Endgame::Kbnk,
+    ),
+    (
+        Material::new(&[Piece::Bishop], &[]),
+        Endgame::InsufficientMaterial,
+    ),
+    (
+        Material::new(&[Piece::Knight], &[]),
+        Endgame::InsufficientMaterial,
+    ),
+    (Material::new(&[], &[]), Endgame::InsufficientMaterial),
+];
+
+/// The known endgame `material` makes, and the side that can win it.
+pub fn classify(material: &Material) -> Option<(Endgame, Color)> {
+    for strong in [Color::White, Color::Black] {
+        let material = match strong {
+            Color::White => *material,
+            Color::Black => material.flipped(),
+        };
+
+        if let Some((_, endgame)) = ENDGAMES.iter().find(|(m, _)| *m == material) {
+            return Some((*endgame, strong));
+        }
+
+        // a bishop and any number of pawns against a lone king
+        let [pawns, knights, bishops, rooks, queens, kings] = material.0[0];
+        if pawns > 0
+            && bishops == 1
+            && knights + rooks + queens == 0
+            && kings == 1
+            && material.0[1] == LONE_KING
+        {
+            return Some((Endgame::WrongBishop, strong));
+        }
+    }
+
+    None
+}
+
+fn distance(a: Square, b: Square) -> i32 {
+    let (ax, ay) = a.coords();
+    let (bx, by) = b.coords();
+    ax.abs_diff(bx).max(ay.abs_diff(by)) as i32
+}
+
+// higher the nearer the lone king is to an edge
+fn push_to_edge(square: Square) -> i32 {
+    let (x, y) = square.coords();
+    let file = x.min(7 - x) as i32;
+    let rank = y.min(7 - y) as i32;
+    90 - (7 * file * file / 2 + 7 * rank * rank / 2)
+}
+
+fn push_close(a: Square, b: Square) -> i32 {
+    140 - 20 * distance(a, b)
+}
+
+// higher the nearer the square is to a1 or h8
+fn push_to_dark_corner(square: Square) -> i32 {
+    let (x, y) = square.coords();
+    (7 - x as i32 - y as i32).abs()
+}
+
+fn is_dark(square: Square) -> bool {
+    let (x, y) = square.coords();
+    (x + y) % 2 == 0
+}
+
+// a square as the strong side sees it, with its pawns going up the board
+fn relative(square: Square, strong: Color) -> Square {
+    match strong {
+        Color::White => square,
+        Color::Black => Square::from_index(square.index() ^ 56).unwrap(),
+    }
+}
+
+fn only(board: &Board, piece: Piece, color: Color) -> Option<Square> {
+    Bitboard(board.pieces(piece) & board.side(color))
+        .iter()
+        .next()
+}
+
+fn material_value(board: &Board, color: Color) -> i32 {
+    Piece::ALL
+        .into_iter()
+        .map(|piece| {
+            let count = (board.pieces(piece) & board.side(color)).count_ones();
+            piece_value(piece) * count as i32
+        })
+        .sum()
+}
+
+impl Endgame {
+    /// The score for the strong side, or `None` to leave the position to
+    /// the usual evaluation.
+    pub fn evaluate(self, board: &Board, strong: Color) -> Option<i32> {
+        let weak = !strong;
+        let strong_king = only(board, Piece::King, strong)?;
+        let weak_king = only(board, Piece::King, weak)?;
+
+        match self {
+            Endgame::InsufficientMaterial => Some(0),
+
+            Endgame::Kpk => {
+                let pawn = relative(only(board, Piece::Pawn, strong)?, strong);
+                let strong_to_move = board.side_to_move() == strong;
+                let won = kpk::probe(
+                    relative(strong_king, strong),
+                    pawn,
+                    relative(weak_king, strong),
+                    strong_to_move,
+                );
+
+                Some(if won {
+                    KNOWN_WIN + PAWN_VALUE + 10 * pawn.rank().index() as i32
+                } else {
+                    0
+                })
+            }
+
+            Endgame::Krk | Endgame::Kqk | Endgame::Kbnk => {
+                // the lone king may have been stalemated
+                if board.side_to_move() == weak
+                    && !board.in_check()
+                    && board.legal_moves().is_empty()
+                {
+                    return Some(0);
+                }
+
+                let mut score =
+                    KNOWN_WIN + material_value(board, strong) + push_close(strong_king, weak_king);
+
+                if self == Endgame::Kbnk {
+                    let bishop = only(board, Piece::Bishop, strong)?;
+                    let weak_king = if is_dark(bishop) {
+                        weak_king
+                    } else {
+                        Square::from_index(weak_king.index() ^ 7).unwrap()
+                    };
+                    score += 40 * push_to_dark_corner(weak_king);
+                } else {
+                    score += push_to_edge(weak_king);
+                }
+
+                Some(score)
+            }
+
+            Endgame::WrongBishop => {
+                let pawns = board.pieces(Piece::Pawn) & board.side(strong);
+                let file = [File::A, File::H]
+                    .into_iter()
+                    .find(|file| pawns & !file.bitboard() == 0)?;
+
+                let promotion = relative(Square::new(file, Rank::R8), strong);
+                let bishop = only(board, Piece::Bishop, strong)?;
+
+                if is_dark(bishop) != is_dark(promotion) && distance(weak_king, promotion) <= 1 {
+                    Some(0)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// The score in centipawns from white's point of view if the material makes
+/// a known endgame.
+pub fn evaluate_white(board: &Board) -> Option<i32> {
+    let (endgame, strong) = classify(&Material::of(board))?;
+    let score = endgame.evaluate(board, strong)?;
+
+    Some(match strong {
+        Color::White => score,
+        Color::Black => -score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::endgame::*;
+
+    #[test]
+    fn test_material() {
+        let board = Board::from("8/8/8/3k4/8/8/8/KBN5 w - - 0 1");
+        let material = Material::of(&board);
+        assert_eq!(material.to_string(), "KBNvK");
+        assert_eq!("KBNvK".parse(), Ok(material));
+        assert_eq!(material.flipped().to_string(), "KvKBN");
+        assert_eq!(classify(&material), Some((Endgame::Kbnk, Color::White)));
+        assert_eq!(
+            classify(&material.flipped()),
+            Some((Endgame::Kbnk, Color::Black))
+        );
+        assert_eq!(
+            classify(&"KBPPvK".parse().unwrap()),
+            Some((Endgame::WrongBishop, Color::White))
+        );
+        assert_eq!(classify(&"KRvKB".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_mating_endgames() {
+        let edge = Board::from("3k4/8/3K4/8/8/8/8/7R w - - 0 1");
+        let center = Board::from("8/8/8/3k4/8/8/8/K6R w - - 0 1");
+        let edge_score = evaluate_white(&edge).unwrap();
+        assert!(edge_score > KNOWN_WIN);
+        assert!(edge_score > evaluate_white(&center).unwrap());
+
+        // a black queen wins for black
+        let board = Board::from("8/8/8/3K4/8/8/8/k6q w - - 0 1");
+        assert!(evaluate_white(&board).unwrap() < -KNOWN_WIN);
+
+        // stalemate is still a draw
+        let board = Board::from("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1");
+        assert_eq!(evaluate_white(&board), Some(0));
+
+        // with a light squared bishop, a8 and h1 are the corners to mate in
+        let board =
+            |king: &str| Board::from(format!("{king}/8/8/8/8/8/8/4KBN1 w - - 0 1").as_str());
+        assert!(evaluate_white(&board("k7")) > evaluate_white(&board("7k")));
+    }
+
+    #[test]
+    fn test_draws() {
+        for fen in [
+            "8/8/8/3k4/8/8/8/KB6 w",
+            "8/8/8/3k4/8/8/8/KN6 b",
+            "8/8/8/3k4/8/8/8/K7 w",
+        ] {
+            let board = Board::from(format!("{fen} - - 0 1").as_str());
+            assert_eq!(evaluate_white(&board), Some(0), "{fen}");
+            assert_eq!(crate::eval::evaluate_white(&board), 0, "{fen}");
+        }
+
+        // a rook pawn against the king in the corner
+        let board = Board::from("7k/8/7K/7P/8/8/8/8 w - - 0 1");
+        assert_eq!(evaluate_white(&board), Some(0));
+        let board = Board::from("3k4/8/3K4/3P4/8/8/8/8 w - - 0 1");
+        assert!(evaluate_white(&board).unwrap() > KNOWN_WIN);
+
+        // the dark bishop can't drive the king from a8
+        let board = Board::from("1k6/8/8/P7/P7/8/8/2B1K3 w - - 0 1");
+        assert_eq!(evaluate_white(&board), Some(0));
+        let board = Board::from("1k6/8/8/P7/P7/8/8/1B2K3 w - - 0 1");
+        assert_eq!(evaluate_white(&board), None);
+
+        // black's h-pawn promotes on h1, which a dark bishop can't cover
+        let board = Board::from("8/8/4k3/8/7p/8/3b2K1/8 w - - 0 1");
+        assert_eq!(evaluate_white(&board), Some(0));
+    }
+}
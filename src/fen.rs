@@ -0,0 +1,401 @@
+// reading and writing FEN. castling rights may be given as KQkq, as X-FEN
+// (KQkq for the outermost rook on each side, the rook's file otherwise) or as
+// Shredder-FEN (always the file), so chess960 positions round-trip. a right
+// without a king and rook to castle with is dropped silently, as older
+// versions accepted them.
+//
+// three-check adds the checks each side has left ("3+3") after the en passant
+// field, or the checks given so far ("+0+0") after the clocks. crazyhouse puts
+// the pockets after the placement, in brackets or as a ninth rank, and marks
+// promoted pieces with a '~'
+
+use crate::castling::home_rank;
+use crate::{Board, CastlingRights, Color, File, Piece, Rank, Square, Variant};
+
+fn piece_from_char(c: char) -> Option<(Piece, Color)> {
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+
+    Some((piece, color))
+}
+
+pub(crate) fn piece_to_char(piece: Piece, color: Color) -> char {
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+
+    match color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+// either the checks left ("2+3") or, with a leading '+', the checks given
+// ("+1+0"), as checks given
+fn parse_checks(field: &str) -> Result<[u8; 2], String> {
+    let invalid = || format!("invalid check counters: {field}");
+
+    let (given, counters) = match field.strip_prefix('+') {
+        Some(counters) => (true, counters),
+        None => (false, field),
+    };
+    let (white, black) = counters.split_once('+').ok_or_else(invalid)?;
+    let white: u8 = white.parse().map_err(|_| invalid())?;
+    let black: u8 = black.parse().map_err(|_| invalid())?;
+
+    if given {
+        Ok([white, black])
+    } else if white <= 3 && black <= 3 {
+        Ok([3 - white, 3 - black])
+    } else {
+        Err(invalid())
+    }
+}
+
+impl Board {
+    /// Reads a position from FEN. A castling right without a king and rook
+    /// to castle with is dropped rather than rejected.
+    pub fn from_fen(fen: &str) -> Result<Board, String> {
+        let mut fields = fen.split_whitespace();
+        let mut board = Board::new();
+        board.castling = CastlingRights::NONE;
+
+        let placement = fields.next().ok_or("empty FEN")?;
+        let (placement, mut pockets) = match placement.split_once('[') {
+            Some((placement, pockets)) => {
+                let pockets = pockets.strip_suffix(']').ok_or("unclosed pockets")?;
+                (placement, Some(pockets))
+            }
+            None => (placement, None),
+        };
+
+        let mut ranks: Vec<_> = placement.split('/').collect();
+        if ranks.len() == 9 && pockets.is_none() {
+            pockets = ranks.pop();
+        }
+        if ranks.len() != 8 {
+            return Err(format!("expected 8 ranks, found {}", ranks.len()));
+        }
+
+        for (rank, row) in Rank::all().rev().zip(ranks) {
+            let mut files = File::all();
+            let mut last = None;
+            for c in row.chars() {
+                if let Some(skip) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    for _ in 0..skip {
+                        files.next().ok_or(format!("rank {rank} is too long"))?;
+                    }
+                    last = None;
+                    continue;
+                }
+
+                if c == '~' {
+                    let square = last.take().ok_or("'~' without a piece before it")?;
+                    board.set_promoted(square);
+                    continue;
+                }
+
+                let (piece, color) = piece_from_char(c).ok_or(format!("invalid piece: {c}"))?;
+                let file = files.next().ok_or(format!("rank {rank} is too long"))?;
+                let square = Square::new(file, rank);
+                board.set(piece, color, square);
+                last = Some(square);
+            }
+
+            if files.next().is_some() {
+                return Err(format!("rank {rank} is too short"));
+            }
+        }
+
+        if let Some(pockets) = pockets {
+            for c in pockets.chars() {
+                match piece_from_char(c) {
+                    Some((Piece::King, _)) | None => {
+                        return Err(format!("invalid piece in pocket: {c}"))
+                    }
+                    Some((piece, color)) => board.pocket_mut(color).add(piece),
+                }
+            }
+            board.variant = Variant::Crazyhouse;
+        }
+
+        board.whites_turn = match fields.next() {
+            Some("w") | None => true,
+            Some("b") => false,
+            Some(side) => return Err(format!("invalid side to move: {side}")),
+        };
+
+        match fields.next() {
+            Some("-") | None => {}
+            Some(castling) => {
+                for c in castling.chars() {
+                    board.add_castling_right(c)?;
+                }
+            }
+        }
+
+        match fields.next() {
+            Some("-") | None => {}
+            Some(square) => board.en_passant_targets = square.parse::<Square>()?.bit(),
+        }
+
+        let mut fields: Vec<_> = fields.collect();
+        if let Some(index) = fields.iter().position(|field| field.contains('+')) {
+            board.checks = parse_checks(fields.remove(index))?;
+            board.variant = Variant::ThreeCheck;
+        }
+        let mut fields = fields.into_iter();
+
+        if let Some(clock) = fields.next() {
+            board.halfmove_clock = clock
+                .parse()
+                .map_err(|_| format!("invalid halfmove clock: {clock}"))?;
+        }
+        if let Some(number) = fields.next() {
+            board.fullmove_number = number
+                .parse()
+                .map_err(|_| format!("invalid fullmove number: {number}"))?;
+        }
+
+        board.chess960 = board.needs_chess960();
+        Ok(board)
+    }
+
+    fn add_castling_right(&mut self, c: char) -> Result<(), String> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let rank = home_rank(color);
+
+        let king = (self.kings & self.side(color) & rank.bitboard()).trailing_zeros();
+        let Some(king) = Square::from_index(king as u8) else {
+            return Ok(());
+        };
+        let own_rook =
+            |file: &File| self.piece_at(Square::new(*file, rank)) == Some((Piece::Rook, color));
+
+        let (kingside, rook) = match c.to_ascii_lowercase() {
+            // the outermost rook on that side
+            'k' => (
+                true,
+                File::all()
+                    .rev()
+                    .take_while(|f| *f > king.file())
+                    .find(own_rook),
+            ),
+            'q' => (
+                false,
+                File::all().take_while(|f| *f < king.file()).find(own_rook),
+            ),
+            c => {
+                let file = File::from_char(c).ok_or(format!("invalid castling right: {c}"))?;
+                (file > king.file(), Some(file).filter(own_rook))
+            }
+        };
+
+        if let Some(rook) = rook {
+            self.castling.set(color, kingside, Some(rook));
+        }
+        Ok(())
+    }
+
+    /// The position in FEN, with castling rights in X-FEN.
+    pub fn fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    /// The position in FEN, with castling rights given by the rooks' files.
+    pub fn shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut fen = String::new();
+
+        for rank in Rank::all().rev() {
+            let mut empty = 0;
+            for file in File::all() {
+                match self.piece_at(Square::new(file, rank)) {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_to_char(piece, color));
+                        if self.promoted().contains(Square::new(file, rank)) {
+                            fen.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank != Rank::R1 {
+                fen.push('/');
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            fen.push('[');
+            for color in [Color::White, Color::Black] {
+                for piece in self.pocket(color).pieces() {
+                    fen.push(piece_to_char(piece, color));
+                }
+            }
+            fen.push(']');
+        }
+
+        fen.push_str(if self.whites_turn { " w " } else { " b " });
+
+        let mut castling = String::new();
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let Some(file) = self.castling.rook(color, kingside) else {
+                    continue;
+                };
+
+                let rank = home_rank(color);
+                let outermost = if kingside {
+                    File::all().rev().find(|f| {
+                        self.piece_at(Square::new(*f, rank)) == Some((Piece::Rook, color))
+                    })
+                } else {
+                    File::all().find(|f| {
+                        self.piece_at(Square::new(*f, rank)) == Some((Piece::Rook, color))
+                    })
+                };
+
+                let c = if shredder || outermost != Some(file) {
+                    file.char()
+                } else if kingside {
+                    'k'
+                } else {
+                    'q'
+                };
+                castling.push(match color {
+                    Color::White => c.to_ascii_uppercase(),
+                    Color::Black => c,
+                });
+            }
+        }
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        match Square::from_index(self.en_passant_targets.trailing_zeros() as u8) {
+            Some(square) => fen.push_str(&format!(" {square} ")),
+            None => fen.push_str(" - "),
+        }
+
+        if self.variant == Variant::ThreeCheck {
+            let [white, black] = self.checks.map(|given| 3u8.saturating_sub(given));
+            fen.push_str(&format!("{white}+{black} "));
+        }
+
+        fen.push_str(&format!("{} {}", self.halfmove_clock, self.fullmove_number));
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 13 60",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().fen(), fen);
+        }
+
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8").unwrap().fen(),
+            "8/8/8/8/8/8/8/8 w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Board::from_fen("").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::from_fen("9/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::from_fen("ppppppppp/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::from_fen("8/8/8/8/8/8/8/8 x - - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - z9 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1").is_err());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/~4K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_castling_without_a_rook() {
+        // the right is dropped, and castling needs the rook
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w Kq - 0 1").unwrap();
+        assert_eq!(board.castling, CastlingRights::NONE);
+        assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(board.uci("e1g1".to_string(), None).is_err());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1").unwrap();
+        assert_eq!(board.castling.rook(Color::White, false), Some(File::A));
+        assert_eq!(board.castling.rook(Color::White, true), None);
+    }
+
+    #[test]
+    fn test_pockets() {
+        let fen = "r1bk3r/pppp1Bpp/2n5/4P3/4n3/8/PPP2PPP/RNB1K2R~[QPnnp] b Q - 0 11";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.variant, Variant::Crazyhouse);
+        assert_eq!(board.pocket(Color::Black).count(Piece::Knight), 2);
+        assert!(board.promoted().contains(Square::H1));
+        assert_eq!(board.fen(), fen);
+
+        // the pockets as a ninth rank
+        let ninth = Board::from_fen(&fen.replace('[', "/").replace(']', "")).unwrap();
+        assert!(ninth == board);
+    }
+
+    #[test]
+    fn test_chess960_castling_fields() {
+        // rooks on b1 and g1 with another on h1: the g1 rook isn't outermost,
+        // so X-FEN names its file
+        let board = Board::from_fen("1r2k1r1/8/8/8/8/8/8/1R2K1RR w GBgb - 0 1").unwrap();
+        assert!(board.chess960);
+        assert_eq!(board.castling.rook(Color::White, true), Some(File::G));
+        assert_eq!(board.castling.rook(Color::White, false), Some(File::B));
+        assert_eq!(board.fen(), "1r2k1r1/8/8/8/8/8/8/1R2K1RR w GQkq - 0 1");
+        assert_eq!(
+            board.shredder_fen(),
+            "1r2k1r1/8/8/8/8/8/8/1R2K1RR w GBgb - 0 1"
+        );
+        assert!(Board::from_fen(&board.fen()).unwrap() == board);
+
+        // Shredder-FEN for a standard position is still standard chess
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1").unwrap();
+        assert!(!board.chess960);
+        assert_eq!(board.fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    }
+}
@@ -0,0 +1,191 @@
+// king and pawn against king, solved the first time it is needed.
+//
+// every position with white's pawn on files a to d (the other files are
+// mirrored) starts out decided when it is obvious: a pawn that promotes
+// without being taken wins, stalemate or losing the pawn draws. the rest is
+// settled by looking one move ahead, over and over until nothing changes.
+// whatever is still undecided then can't be won, so it is a draw.
+
+use std::sync::OnceLock;
+
+use crate::attacks::{KING_ATTACKS, PAWN_ATTACKS};
+use crate::Square;
+
+// both sides to move, 24 pawn squares and both kings
+const SIZE: usize = 2 * 24 * 64 * 64;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+static WINS: OnceLock<Vec<u64>> = OnceLock::new();
+
+fn index(white_to_move: bool, white_king: usize, pawn: usize, black_king: usize) -> usize {
+    white_king
+        | black_king << 6
+        | (!white_to_move as usize) << 12
+        | (pawn & 7) << 13
+        | (6 - (pawn >> 3)) << 15
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    let files = (a & 7).abs_diff(b & 7);
+    let ranks = (a >> 3).abs_diff(b >> 3);
+    files.max(ranks)
+}
+
+struct Position {
+    white_to_move: bool,
+    white_king: usize,
+    pawn: usize,
+    black_king: usize,
+}
+
+impl Position {
+    fn new(index: usize) -> Position {
+        Position {
+            white_to_move: (index >> 12) & 1 == 0,
+            white_king: index & 63,
+            pawn: ((index >> 13) & 3) + 8 * (6 - ((index >> 15) & 7)),
+            black_king: (index >> 6) & 63,
+        }
+    }
+
+    fn initial(&self) -> u8 {
+        let (white_king, pawn, black_king) = (self.white_king, self.pawn, self.black_king);
+        let black_moves = KING_ATTACKS[black_king];
+        let white_guards = KING_ATTACKS[white_king] | PAWN_ATTACKS[0][pawn];
+
+        if distance(white_king, black_king) <= 1
+            || white_king == pawn
+            || black_king == pawn
+            || (self.white_to_move && PAWN_ATTACKS[0][pawn] & 1 << black_king != 0)
+        {
+            INVALID
+        } else if self.white_to_move
+            && pawn >> 3 == 6
+            && white_king != pawn + 8
+            && (distance(black_king, pawn + 8) > 1 || distance(white_king, pawn + 8) == 1)
+        {
+            WIN
+        } else if !self.white_to_move
+            && (black_moves & !white_guards == 0
+                || black_moves & !KING_ATTACKS[white_king] & 1 << pawn != 0)
+        {
+            DRAW
+        } else {
+            UNKNOWN
+        }
+    }
+
+    // a win for white if any white move wins, a draw if any black move
+    // draws, and otherwise decided only once every move is
+    fn classify(&self, results: &[u8]) -> u8 {
+        let mut seen = INVALID;
+
+        if self.white_to_move {
+            for king in crate::Bitboard(KING_ATTACKS[self.white_king]) {
+                let king = king.index() as usize;
+                seen |= results[index(false, king, self.pawn, self.black_king)];
+            }
+
+            let push = self.pawn + 8;
+            if push >> 3 < 7 {
+                seen |= results[index(false, self.white_king, push, self.black_king)];
+            }
+            if self.pawn >> 3 == 1 && push != self.white_king && push != self.black_king {
+                seen |= results[index(false, self.white_king, push + 8, self.black_king)];
+            }
+        } else {
+            for king in crate::Bitboard(KING_ATTACKS[self.black_king]) {
+                let king = king.index() as usize;
+                seen |= results[index(true, self.white_king, self.pawn, king)];
+            }
+        }
+
+        let (good, bad) = if self.white_to_move {
+            (WIN, DRAW)
+        } else {
+            (DRAW, WIN)
+        };
+
+        if seen & good != 0 {
+            good
+        } else if seen & UNKNOWN != 0 {
+            UNKNOWN
+        } else {
+            bad
+        }
+    }
+}
+
+fn wins() -> &'static [u64] {
+    WINS.get_or_init(|| {
+        let positions: Vec<Position> = (0..SIZE).map(Position::new).collect();
+        let mut results: Vec<u8> = positions.iter().map(Position::initial).collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, position) in positions.iter().enumerate() {
+                if results[i] == UNKNOWN {
+                    results[i] = position.classify(&results);
+                    changed |= results[i] != UNKNOWN;
+                }
+            }
+        }
+
+        let mut wins = vec![0; SIZE / 64];
+        for (i, result) in results.into_iter().enumerate() {
+            if result == WIN {
+                wins[i / 64] |= 1 << (i % 64);
+            }
+        }
+        wins
+    })
+}
+
+/// Whether white wins with a king and a pawn against black's king. The
+/// position has to be legal, with the pawn on the second to seventh rank.
+pub fn probe(white_king: Square, pawn: Square, black_king: Square, white_to_move: bool) -> bool {
+    let mut squares = [white_king, pawn, black_king].map(|square| square.index() as usize);
+    if squares[1] & 7 > 3 {
+        squares = squares.map(|square| square ^ 7);
+    }
+
+    let [white_king, pawn, black_king] = squares;
+    let i = index(white_to_move, white_king, pawn, black_king);
+    wins()[i / 64] & 1 << (i % 64) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kpk::*;
+
+    #[test]
+    fn test_kpk() {
+        // the number of won positions any kpk generator arrives at
+        let count: u32 = wins().iter().map(|bits| bits.count_ones()).sum();
+        assert_eq!(count, 111282);
+
+        // the king in front of its pawn on the sixth rank wins whoever moves
+        assert!(probe(Square::D6, Square::D5, Square::D8, true));
+        assert!(probe(Square::D6, Square::D5, Square::D8, false));
+
+        // with the king on the fifth it comes down to the opposition
+        assert!(!probe(Square::E5, Square::E4, Square::E7, true));
+        assert!(probe(Square::E5, Square::E4, Square::E7, false));
+
+        // and a king blocking the pawn on the sixth holds
+        assert!(!probe(Square::D5, Square::D6, Square::D7, true));
+
+        // and a rook pawn can't make it with the black king in the corner
+        assert!(!probe(Square::H6, Square::H5, Square::H8, true));
+        assert!(!probe(Square::B6, Square::A5, Square::A8, false));
+
+        // a pawn out of reach of the black king just runs
+        assert!(probe(Square::A1, Square::G5, Square::A7, true));
+        assert!(!probe(Square::A1, Square::G5, Square::F7, false));
+    }
+}
@@ -9,17 +9,49 @@
 // 1 0 1 0 1 0 1 0
 // 1 1 1 1 1 1 1 1
 
-use std::cmp::max;
+use attacks::{leaper_attacks, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+pub use bitboard::Bitboard;
+pub use builder::BoardBuilder;
+pub use castling::CastlingRights;
+pub use crazyhouse::Pocket;
+use magic::{attacks_from, bishop_attacks, queen_attacks, rook_attacks};
+pub use retro::Unmove;
+pub use square::{File, Rank, Square};
+pub use undo::Undo;
+pub use validate::PositionError;
+pub use variant::{Outcome, Variant};
+
+pub mod attacks;
+pub mod bitboard;
+pub mod builder;
+pub mod castling;
+pub mod crazyhouse;
+pub mod endgame;
+pub mod eval;
+mod fen;
+pub mod kpk;
+pub mod magic;
+pub mod movepick;
+pub mod problem;
+#[cfg(test)]
+mod properties;
+mod retro;
+mod san;
+pub mod search;
+mod see;
+pub mod square;
+pub mod symmetry;
+pub mod syzygy;
+pub mod tablebase;
+mod transform;
+mod undo;
+pub mod validate;
+pub mod variant;
+mod zobrist;
 
 #[allow(unused)]
 fn print_bitboard(bb: u64) {
-    let board = bitboard_to_grid(bb);
-    for row in board {
-        for bit in row {
-            print!("{} ", if bit { '1' } else { '0' });
-        }
-        println!();
-    }
+    print!("{}", Bitboard(bb));
 }
 
 pub fn bitboard_to_grid(mut bb: u64) -> [[bool; 8]; 8] {
@@ -61,32 +93,25 @@ pub fn grid_to_coords(grid: [[bool; 8]; 8]) -> Vec<(u8, u8)> {
 
 pub fn print_board(board: Board) {
     println!();
-    for y in 0..8 {
-        for x in 0..8 {
-            if !get_bit(board.white | board.black, x, y) {
+    for rank in Rank::all() {
+        for file in File::all() {
+            let Some((piece, color)) = board.piece_at(Square::new(file, rank)) else {
                 print!(". ");
                 continue;
-            }
+            };
 
-            let white = get_bit(board.white, x, y);
-
-            let piece = if get_bit(board.pawns, x, y) {
-                'p'
-            } else if get_bit(board.knights, x, y) {
-                'n'
-            } else if get_bit(board.bishops, x, y) {
-                'b'
-            } else if get_bit(board.rooks, x, y) {
-                'r'
-            } else if get_bit(board.queens, x, y) {
-                'q'
-            } else {
-                'k'
+            let piece = match piece {
+                Piece::Pawn => 'p',
+                Piece::Knight => 'n',
+                Piece::Bishop => 'b',
+                Piece::Rook => 'r',
+                Piece::Queen => 'q',
+                Piece::King => 'k',
             };
 
             print!(
                 "{} ",
-                if white {
+                if color == Color::White {
                     piece.to_ascii_uppercase()
                 } else {
                     piece
@@ -98,24 +123,36 @@ pub fn print_board(board: Board) {
     }
 }
 
+// every file that a piece can land on after moving `i` squares left / right
+// (anything else wrapped around to the previous / next rank)
+#[inline]
+fn files_left_of(i: u8) -> u64 {
+    (0xFFu64 >> i.min(8)) * 0x0101010101010101
+}
+
+#[inline]
+fn files_right_of(i: u8) -> u64 {
+    ((0xFFu64 << i.min(8)) & 0xFF) * 0x0101010101010101
+}
+
 #[inline]
 pub fn bitboard_shl(bb: u64, i: u8) -> u64 {
-    (bb >> i) & 0b0111111101111111011111110111111101111111011111110111111101111111
+    bb.checked_shr(i as u32).unwrap_or(0) & files_left_of(i)
 }
 
 #[inline]
 pub fn bitboard_shr(bb: u64, i: u8) -> u64 {
-    (bb << i) & 0b1111111011111110111111101111111011111110111111101111111011111110
+    bb.checked_shl(i as u32).unwrap_or(0) & files_right_of(i)
 }
 
 #[inline]
 pub fn bitboard_shu(bb: u64, i: u8) -> u64 {
-    bb >> (i * 8)
+    bb.checked_shr(i as u32 * 8).unwrap_or(0)
 }
 
 #[inline]
 pub fn bitboard_shd(bb: u64, i: u8) -> u64 {
-    bb << (i * 8)
+    bb.checked_shl(i as u32 * 8).unwrap_or(0)
 }
 
 #[inline]
@@ -138,6 +175,24 @@ pub fn bitboard_shdr(bb: u64, i: u8) -> u64 {
     bitboard_shd(bitboard_shr(bb, i), i)
 }
 
+// rank 1 <-> rank 8, one byte per rank
+#[inline]
+pub fn bitboard_flip_vertical(bb: u64) -> u64 {
+    bb.swap_bytes()
+}
+
+// a-file <-> h-file: reversing the bits turns the board around, and swapping
+// the ranks back leaves only the files mirrored
+#[inline]
+pub fn bitboard_flip_horizontal(bb: u64) -> u64 {
+    bb.reverse_bits().swap_bytes()
+}
+
+#[inline]
+pub fn bitboard_rotate_180(bb: u64) -> u64 {
+    bb.reverse_bits()
+}
+
 #[inline]
 pub fn bitxy(x: u8, y: u8) -> u64 {
     if x == 0 && y == 0 {
@@ -223,28 +278,36 @@ pub fn pawns_down(bb: u64, stop: u64) -> u64 {
 
 #[inline]
 pub fn pawns_double_up(bb: u64, stop: u64) -> u64 {
-    !stop
-        & pawns_up(
-            !stop
-                & pawns_up(
-                    0b0000000011111111000000000000000000000000000000000000000000000000 & bb,
-                    stop,
-                ),
-            stop,
-        )
+    pawns_double_up_from(
+        bb,
+        stop,
+        0b0000000011111111000000000000000000000000000000000000000000000000,
+    )
 }
 
 #[inline]
 pub fn pawns_double_down(bb: u64, stop: u64) -> u64 {
-    pawns_down(
-        pawns_down(
-            0b0000000000000000000000000000000000000000000000001111111100000000 & bb,
-            stop,
-        ),
+    pawns_double_down_from(
+        bb,
         stop,
+        0b0000000000000000000000000000000000000000000000001111111100000000,
     )
 }
 
+// double pushes for the pawns on `ranks` only: normally the second (or
+// seventh) rank, but horde lets white's first rank pawns do it too
+#[inline]
+pub fn pawns_double_up_from(bb: u64, stop: u64, ranks: u64) -> u64 {
+    !stop & pawns_up(!stop & pawns_up(ranks & bb, stop), stop)
+}
+
+#[inline]
+pub fn pawns_double_down_from(bb: u64, stop: u64, ranks: u64) -> u64 {
+    pawns_down(pawns_down(ranks & bb, stop), stop)
+}
+
+// the shift based attacks below are the reference the tables in `attacks` are
+// tested against. move generation and check detection use the tables
 #[inline]
 pub fn pawns_capture_up(bb: u64, stop: u64) -> u64 {
     !stop & (bitboard_shul(bb, 1) | bitboard_shur(bb, 1))
@@ -281,94 +344,104 @@ pub fn king_moves(bb: u64, stop: u64) -> u64 {
             | bitboard_shr(bb, 1))
 }
 
+// every square `color`'s knights, sliders and king attack, other than its own
+fn piece_attacks(board: Board, color: Color) -> u64 {
+    let (mine, occupied) = (board.side(color), board.white | board.black);
+    (leaper_attacks(board.knights & mine, &KNIGHT_ATTACKS)
+        | attacks_from(board.bishops & mine, occupied, bishop_attacks)
+        | attacks_from(board.rooks & mine, occupied, rook_attacks)
+        | attacks_from(board.queens & mine, occupied, queen_attacks)
+        | leaper_attacks(board.kings & mine, &KING_ATTACKS))
+        & !mine
+}
+
 #[inline]
 pub fn white_moves(board: Board) -> u64 {
-    pawns_up(board.pawns & board.white, board.white)
-        | pawns_double_down(board.pawns & board.white, board.white)
-        | knight_moves(board.knights & board.white, board.white)
-        | (slide_diag(board.bishops & board.white, board.white | board.black) & !board.white)
-        | (slide_horiz(board.rooks & board.white, board.white | board.black) & !board.white)
-        | (slide_all(board.queens & board.white, board.white | board.black) & !board.white)
-        | king_moves(board.kings & board.white, board.white)
+    pawns_down(board.pawns & board.white, board.white | board.black)
+        | pawns_double_down(board.pawns & board.white, board.white | board.black)
+        | piece_attacks(board, Color::White)
 }
 
 #[inline]
 pub fn black_moves(board: Board) -> u64 {
-    pawns_up(board.pawns & board.black, board.black)
-        | pawns_double_down(board.pawns & board.black, board.black)
-        | knight_moves(board.knights & board.black, board.black)
-        | (slide_diag(board.bishops & board.black, board.black | board.white) & !board.black)
-        | (slide_horiz(board.rooks & board.black, board.black | board.white) & !board.black)
-        | (slide_all(board.queens & board.black, board.black | board.white) & !board.black)
-        | king_moves(board.kings & board.black, board.black)
+    pawns_up(board.pawns & board.black, board.black | board.white)
+        | pawns_double_up(board.pawns & board.black, board.black | board.white)
+        | piece_attacks(board, Color::Black)
 }
 
 #[inline]
 pub fn white_captures(board: Board) -> u64 {
-    pawns_capture_down(board.pawns & board.white, board.white)
-        | knight_moves(board.knights & board.white, board.white)
-        | (slide_diag(board.bishops & board.white, board.white | board.black) & !board.white)
-        | (slide_horiz(board.rooks & board.white, board.white | board.black) & !board.white)
-        | (slide_all(board.queens & board.white, board.white | board.black) & !board.white)
-        | king_moves(board.kings & board.white, board.white)
+    leaper_attacks(
+        board.pawns & board.white,
+        &PAWN_ATTACKS[Color::White as usize],
+    ) & !board.white
+        | piece_attacks(board, Color::White)
 }
 
 #[inline]
 pub fn black_captures(board: Board) -> u64 {
-    pawns_capture_up(board.pawns & board.black, board.black)
-        | knight_moves(board.knights & board.black, board.black)
-        | (slide_diag(board.bishops & board.black, board.white | board.black) & !board.black)
-        | (slide_horiz(board.rooks & board.black, board.white | board.black) & !board.black)
-        | (slide_all(board.queens & board.black, board.white | board.black) & !board.black)
-        | king_moves(board.kings & board.black, board.black)
+    leaper_attacks(
+        board.pawns & board.black,
+        &PAWN_ATTACKS[Color::Black as usize],
+    ) & !board.black
+        | piece_attacks(board, Color::Black)
 }
 
-pub fn piece_moves(board: Board, x: u8, y: u8) -> u64 {
+pub fn piece_moves_xy(board: Board, x: u8, y: u8) -> u64 {
+    match Square::from_coords(x, y) {
+        Some(square) => piece_moves(board, square),
+        None => 0,
+    }
+}
+
+pub fn piece_moves(board: Board, square: Square) -> u64 {
+    let (x, y) = square.coords();
     if !get_bit(board.white | board.black, x, y) {
         return 0;
     }
 
-    let bit = bitxy(x, y);
+    let bit = square.bit();
+    let index = square.index() as usize;
     let white = get_bit(board.white, x, y);
 
-    let (mine, other, castle) = if white {
-        (board.white, board.black, board.white_can_castle)
+    let (mine, other) = if white {
+        (board.white, board.black)
     } else {
-        (board.black, board.white, board.black_can_castle)
+        (board.black, board.white)
     };
 
     if get_bit(board.pawns, x, y) {
         if white {
+            let ranks = board.double_push_ranks(Color::White);
             !mine
                 & (pawns_down(bit, other)
-                    | pawns_double_down(bit, mine | other)
-                    | ((board.en_passant_targets | other) & pawns_capture_down(bit, mine)))
+                    | pawns_double_down_from(bit, mine | other, ranks)
+                    | ((board.en_passant_targets | other)
+                        & PAWN_ATTACKS[Color::White as usize][index]))
         } else {
+            let ranks = board.double_push_ranks(Color::Black);
             !mine
                 & (pawns_up(bit, other)
-                    | pawns_double_up(bit, mine | other)
-                    | ((board.en_passant_targets | other) & pawns_capture_up(bit, mine)))
+                    | pawns_double_up_from(bit, mine | other, ranks)
+                    | ((board.en_passant_targets | other)
+                        & PAWN_ATTACKS[Color::Black as usize][index]))
         }
     } else if get_bit(board.knights, x, y) {
-        knight_moves(bit, mine)
+        !mine & KNIGHT_ATTACKS[index]
     } else if get_bit(board.bishops, x, y) {
-        !mine & slide_diag(bit, mine | other)
+        !mine & bishop_attacks(square, mine | other)
     } else if get_bit(board.rooks, x, y) {
-        !mine & slide_horiz(bit, mine | other)
+        !mine & rook_attacks(square, mine | other)
     } else if get_bit(board.queens, x, y) {
-        !mine & slide_all(bit, mine | other)
+        !mine & queen_attacks(square, mine | other)
     } else if get_bit(board.kings, x, y) {
-        !mine & king_moves(bit, mine)
-            | (if castle.0 {
-                set_bit(0, 6, if white { 0 } else { 7 })
-            } else {
-                0
-            })
-            | (if castle.1 {
-                set_bit(0, 2, if white { 0 } else { 7 })
-            } else {
-                0
-            })
+        let color = if white { Color::White } else { Color::Black };
+        // an atomic king would blow itself up by capturing
+        let stop = match board.variant {
+            Variant::Atomic => mine | other,
+            _ => mine,
+        };
+        !stop & KING_ATTACKS[index] | board.castling_targets(square, color)
     } else {
         0
     }
@@ -382,7 +455,7 @@ pub fn white_piece_moves(board: Board) -> Vec<((u8, u8), (u8, u8))> {
                 continue;
             }
 
-            let moves = grid_to_coords(bitboard_to_grid(piece_moves(board, x, y)));
+            let moves = grid_to_coords(bitboard_to_grid(piece_moves_xy(board, x, y)));
 
             for m in moves {
                 all.push(((x, y), m));
@@ -401,7 +474,7 @@ pub fn black_piece_moves(board: Board) -> Vec<((u8, u8), (u8, u8))> {
                 continue;
             }
 
-            let moves = grid_to_coords(bitboard_to_grid(piece_moves(board, x, y)));
+            let moves = grid_to_coords(bitboard_to_grid(piece_moves_xy(board, x, y)));
 
             for m in moves {
                 all.push(((x, y), m));
@@ -414,6 +487,9 @@ pub fn black_piece_moves(board: Board) -> Vec<((u8, u8), (u8, u8))> {
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Board {
+    // what is on each square (`x + y * 8`), kept in step with the bitboards
+    mailbox: [Option<(Piece, Color)>; 64],
+
     white: u64,
     black: u64,
     pawns: u64,
@@ -425,15 +501,33 @@ pub struct Board {
 
     pub en_passant_targets: u64,
 
-    pub white_can_castle: (bool, bool),
-    pub black_can_castle: (bool, bool),
+    pub castling: CastlingRights,
+    /// Castling is written as the king taking its rook, in UCI and in
+    /// `move_piece`, and the king and rooks may start on any file.
+    pub chess960: bool,
 
     pub whites_turn: bool,
+
+    pub variant: Variant,
+    /// Checks given by each colour, indexed by [`Color`], for three-check.
+    pub checks: [u8; 2],
+    // crazyhouse pockets, indexed by colour, and the pieces that were pawns
+    pockets: [Pocket; 2],
+    promoted: u64,
+    // the zobrist keys of the pieces on the board, kept in step with the
+    // bitboards by `set` and `unset`
+    hash: u64,
+
+    /// Half moves since the last capture or pawn move, for the fifty move rule.
+    pub halfmove_clock: u32,
+    /// Starts at 1 and goes up after every black move.
+    pub fullmove_number: u32,
 }
 
 impl Default for Board {
     fn default() -> Self {
-        Self {
+        let mut board = Self {
+            mailbox: [None; 64],
             white: 0b0000000000000000000000000000000000000000000000001111111111111111,
             black: 0b1111111111111111000000000000000000000000000000000000000000000000,
             pawns: 0b0000000011111111000000000000000000000000000000001111111100000000,
@@ -444,21 +538,48 @@ impl Default for Board {
             kings: 0b0001000000000000000000000000000000000000000000000000000000010000,
             en_passant_targets: 0,
 
-            white_can_castle: (true, true),
-            black_can_castle: (true, true),
+            castling: CastlingRights::STANDARD,
+            chess960: false,
 
             whites_turn: true,
+
+            variant: Variant::Standard,
+            checks: [0; 2],
+            pockets: [Pocket::EMPTY; 2],
+            promoted: 0,
+            hash: 0,
+
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        };
+
+        for square in Square::all() {
+            board.mailbox[square.index() as usize] = board.find_piece(square);
         }
+        board.hash = board.placement_hash();
+
+        board
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
     White,
     Black,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+impl std::ops::Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Piece {
     Pawn,
     Knight,
@@ -468,48 +589,77 @@ pub enum Piece {
     King,
 }
 
-impl From<&str> for Board {
-    // TODO: i hate `fen`'s error handling, but i have to deal with it someday
-    fn from(value: &str) -> Self {
-        let mut board = Board::new();
+impl Piece {
+    pub const ALL: [Piece; 6] = [
+        Piece::Pawn,
+        Piece::Knight,
+        Piece::Bishop,
+        Piece::Rook,
+        Piece::Queen,
+        Piece::King,
+    ];
+}
 
-        let state = fen::BoardState::from_fen(value).unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promote: Option<Piece>,
+    /// The piece dropped from the pocket onto `to`, in crazyhouse. `from` is
+    /// the same square as `to`.
+    pub drop: Option<Piece>,
+}
 
-        board.white_can_castle = (state.white_can_oo, state.white_can_ooo);
-        board.black_can_castle = (state.black_can_oo, state.black_can_ooo);
+impl Move {
+    pub fn new(from: Square, to: Square, promote: Option<Piece>) -> Self {
+        Self {
+            from,
+            to,
+            promote,
+            drop: None,
+        }
+    }
 
-        if let Some(s) = state.en_passant_square {
-            board.en_passant_targets = 1 << s;
+    pub fn drop(piece: Piece, to: Square) -> Self {
+        Self {
+            from: to,
+            to,
+            promote: None,
+            drop: Some(piece),
         }
+    }
 
-        for (i, piece) in state.pieces.into_iter().enumerate() {
-            if let Some(piece) = piece {
-                if piece.color == fen::Color::White {
-                    board.white |= 1 << i;
-                } else {
-                    board.black |= 1 << i;
-                }
+    pub fn uci(&self) -> String {
+        if let Some(piece) = self.drop {
+            return format!("{}@{}", fen::piece_to_char(piece, Color::White), self.to);
+        }
 
-                match piece.kind {
-                    fen::PieceKind::Pawn => board.pawns |= 1 << i,
-                    fen::PieceKind::Knight => board.knights |= 1 << i,
-                    fen::PieceKind::Bishop => board.bishops |= 1 << i,
-                    fen::PieceKind::Rook => board.rooks |= 1 << i,
-                    fen::PieceKind::Queen => board.queens |= 1 << i,
-                    fen::PieceKind::King => board.kings |= 1 << i,
-                }
-            }
+        let mut uci = format!("{}{}", self.from, self.to);
+
+        if let Some(piece) = self.promote {
+            uci.push(match piece {
+                Piece::Knight => 'n',
+                Piece::Bishop => 'b',
+                Piece::Rook => 'r',
+                Piece::King => 'k',
+                _ => 'q',
+            });
         }
 
-        board.whites_turn = state.side_to_play == fen::Color::White;
+        uci
+    }
+}
 
-        board
+impl From<&str> for Board {
+    fn from(value: &str) -> Self {
+        Board::from_fen(value).unwrap()
     }
 }
 
 impl Board {
     pub fn new() -> Self {
         Self {
+            mailbox: [None; 64],
             white: 0,
             black: 0,
             pawns: 0,
@@ -520,46 +670,66 @@ impl Board {
             kings: 0,
             en_passant_targets: 0,
 
-            white_can_castle: (true, true),
-            black_can_castle: (true, true),
+            castling: CastlingRights::STANDARD,
+            chess960: false,
 
             whites_turn: true,
+
+            variant: Variant::Standard,
+            checks: [0; 2],
+            pockets: [Pocket::EMPTY; 2],
+            promoted: 0,
+            hash: 0,
+
+            halfmove_clock: 0,
+            fullmove_number: 1,
         }
     }
 
     pub fn as_grid(&self) -> [[(Option<Piece>, Color); 8]; 8] {
         let mut grid = [[(None, Color::Black); 8]; 8];
-        for y in 0..8 {
-            for x in 0..8 {
-                let c = if get_bit(self.white, x, y) {
-                    Color::White
-                } else {
-                    Color::Black
-                };
-                let p = if get_bit(self.pawns, x, y) {
-                    Some(Piece::Pawn)
-                } else if get_bit(self.knights, x, y) {
-                    Some(Piece::Knight)
-                } else if get_bit(self.bishops, x, y) {
-                    Some(Piece::Bishop)
-                } else if get_bit(self.rooks, x, y) {
-                    Some(Piece::Rook)
-                } else if get_bit(self.queens, x, y) {
-                    Some(Piece::Queen)
-                } else if get_bit(self.kings, x, y) {
-                    Some(Piece::King)
-                } else {
-                    None
-                };
-
-                grid[x as usize][y as usize] = (p, c);
-            }
+        for square in Square::all() {
+            let (x, y) = square.coords();
+            grid[x as usize][y as usize] = match self.piece_at(square) {
+                Some((piece, color)) => (Some(piece), color),
+                None => (None, Color::Black),
+            };
         }
 
         grid
     }
 
-    pub fn set(&mut self, piece: Piece, color: Color, x: u8, y: u8) {
+    /// The piece on `square` and its colour.
+    #[inline]
+    pub fn piece_at(&self, square: Square) -> Option<(Piece, Color)> {
+        self.mailbox[square.index() as usize]
+    }
+
+    // the slow way, from the bitboards alone
+    fn find_piece(&self, square: Square) -> Option<(Piece, Color)> {
+        let piece = Piece::ALL
+            .into_iter()
+            .find(|piece| self.pieces(*piece) & square.bit() != 0)?;
+        let color = if self.white & square.bit() != 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        Some((piece, color))
+    }
+
+    pub fn set_xy(&mut self, piece: Piece, color: Color, x: u8, y: u8) {
+        let square = Square::from_coords(x, y).expect("coordinates must be within `0..=7`");
+        self.set(piece, color, square);
+    }
+
+    pub fn set(&mut self, piece: Piece, color: Color, square: Square) {
+        self.unset(square);
+
+        let (x, y) = square.coords();
+        self.mailbox[square.index() as usize] = Some((piece, color));
+        self.hash ^= zobrist::piece_key(piece, color, square.index());
         match color {
             Color::White => {
                 self.white = set_bit(self.white, x, y);
@@ -581,7 +751,14 @@ impl Board {
         }
     }
 
-    pub fn unset(&mut self, x: u8, y: u8) {
+    pub fn unset_xy(&mut self, x: u8, y: u8) {
+        if let Some(square) = Square::from_coords(x, y) {
+            self.unset(square);
+        }
+    }
+
+    pub fn unset(&mut self, square: Square) {
+        let (x, y) = square.coords();
         self.white = unset_bit(self.white, x, y);
         self.black = unset_bit(self.black, x, y);
         self.pawns = unset_bit(self.pawns, x, y);
@@ -590,6 +767,9 @@ impl Board {
         self.rooks = unset_bit(self.rooks, x, y);
         self.queens = unset_bit(self.queens, x, y);
         self.kings = unset_bit(self.kings, x, y);
+        if let Some((piece, color)) = self.mailbox[square.index() as usize].take() {
+            self.hash ^= zobrist::piece_key(piece, color, square.index());
+        }
     }
 
     pub fn check(&self) -> (bool, bool) {
@@ -608,32 +788,19 @@ impl Board {
             for ((sx, sy), (dx, dy)) in white_piece_moves(*self) {
                 let mut test = *self;
 
-                let piece = if get_bit(self.pawns, sx, sy) {
-                    Piece::Pawn
-                } else if get_bit(self.knights, sx, sy) {
-                    Piece::Knight
-                } else if get_bit(self.bishops, sx, sy) {
-                    Piece::Bishop
-                } else if get_bit(self.rooks, sx, sy) {
-                    Piece::Rook
-                } else if get_bit(self.queens, sx, sy) {
-                    Piece::Queen
-                } else if get_bit(self.kings, sx, sy) {
-                    // castling not allowed in check
-                    if (sx, sy) == (4, 0) && ((dx, dy) == (2, 0) || (dx, dy) == (6, 0)) {
-                        continue;
-                    }
-                    Piece::King
-                } else {
-                    panic!("internal error: {sx},{sy} is not empty, but is not a valid piece type");
+                let Some((piece, _)) = Square::from_coords(sx, sy).and_then(|sq| self.piece_at(sq))
+                else {
+                    panic!("internal error: {sx},{sy} has a move but no piece");
                 };
 
-                test.white = unset_bit(test.white, sx, sy);
-                test.black = unset_bit(test.black, sx, sy);
+                // castling (the king taking its own rook) not allowed in check
+                if piece == Piece::King && get_bit(self.white, dx, dy) {
+                    continue;
+                }
 
-                test.unset(dx, dy);
-                test.unset(sx, sy);
-                test.set(piece, Color::White, dx, dy);
+                test.unset_xy(dx, dy);
+                test.unset_xy(sx, sy);
+                test.set_xy(piece, Color::White, dx, dy);
 
                 if get_bit(test.en_passant_targets, dx, dy) {
                     test.en_passant_targets = unset_bit(test.en_passant_targets, dx, dy);
@@ -641,7 +808,7 @@ impl Board {
                     // if you took an en passant target, the target has to be
                     // on a certain rank (dependent on color), and on the same file
                     // that you're moving to
-                    test.unset(dx, 4);
+                    test.unset_xy(dx, 4);
                 }
 
                 if !test.check().0 {
@@ -654,32 +821,19 @@ impl Board {
             for ((sx, sy), (dx, dy)) in black_piece_moves(*self) {
                 let mut test = *self;
 
-                let piece = if get_bit(self.pawns, sx, sy) {
-                    Piece::Pawn
-                } else if get_bit(self.knights, sx, sy) {
-                    Piece::Knight
-                } else if get_bit(self.bishops, sx, sy) {
-                    Piece::Bishop
-                } else if get_bit(self.rooks, sx, sy) {
-                    Piece::Rook
-                } else if get_bit(self.queens, sx, sy) {
-                    Piece::Queen
-                } else if get_bit(self.kings, sx, sy) {
-                    // castling not allowed in check
-                    if (sx, sy) == (4, 7) && ((dx, dy) == (2, 7) || (dx, dy) == (7, 5)) {
-                        continue;
-                    }
-                    Piece::King
-                } else {
-                    panic!("internal error: {sx},{sy} is not empty, but is not a valid piece type");
+                let Some((piece, _)) = Square::from_coords(sx, sy).and_then(|sq| self.piece_at(sq))
+                else {
+                    panic!("internal error: {sx},{sy} has a move but no piece");
                 };
 
-                test.white = unset_bit(test.white, sx, sy);
-                test.black = unset_bit(test.black, sx, sy);
+                // castling (the king taking its own rook) not allowed in check
+                if piece == Piece::King && get_bit(self.black, dx, dy) {
+                    continue;
+                }
 
-                test.unset(dx, dy);
-                test.unset(sx, sy);
-                test.set(piece, Color::Black, dx, dy);
+                test.unset_xy(dx, dy);
+                test.unset_xy(sx, sy);
+                test.set_xy(piece, Color::Black, dx, dy);
 
                 if get_bit(test.en_passant_targets, dx, dy) {
                     test.en_passant_targets = unset_bit(test.en_passant_targets, dx, dy);
@@ -687,7 +841,7 @@ impl Board {
                     // if you took an en passant target, the target has to be
                     // on a certain rank (dependent on color), and on the same file
                     // that you're moving to
-                    test.unset(dx, 3);
+                    test.unset_xy(dx, 3);
                 }
 
                 if !test.check().1 {
@@ -699,7 +853,7 @@ impl Board {
         (white, black)
     }
 
-    pub fn move_piece(
+    pub fn move_piece_xy(
         &mut self,
         sx: u8,
         sy: u8,
@@ -707,15 +861,34 @@ impl Board {
         dy: u8,
         promote: Option<Piece>,
     ) -> Result<(), String> {
-        if max(max(sx, sy), max(dx, dy)) > 7 {
-            return Err("coordinates must be within the range `1..=7`".to_string());
+        match (Square::from_coords(sx, sy), Square::from_coords(dx, dy)) {
+            (Some(from), Some(to)) => self.move_piece(from, to, promote),
+            _ => Err("coordinates must be within the range `0..=7`".to_string()),
         }
+    }
 
-        if !get_bit(self.white | self.black, sx, sy) {
-            return Err(format!("square {sx},{sy} is empty and cannot move"));
-        }
+    pub fn move_piece(
+        &mut self,
+        from: Square,
+        to: Square,
+        promote: Option<Piece>,
+    ) -> Result<(), String> {
+        self.make_move(from, to, promote, &mut Undo::new(self))
+    }
+
+    // `move_piece`, recording what it changes in `undo`
+    pub(crate) fn make_move(
+        &mut self,
+        from: Square,
+        to: Square,
+        promote: Option<Piece>,
+        undo: &mut Undo,
+    ) -> Result<(), String> {
+        let Some((piece, color)) = self.piece_at(from) else {
+            return Err(format!("square {from} is empty and cannot move"));
+        };
 
-        let white = get_bit(self.white, sx, sy);
+        let white = color == Color::White;
 
         if white != self.whites_turn {
             return Err(format!(
@@ -724,167 +897,115 @@ impl Board {
             ));
         }
 
-        let piece = if get_bit(self.pawns, sx, sy) {
-            Piece::Pawn
-        } else if get_bit(self.knights, sx, sy) {
-            Piece::Knight
-        } else if get_bit(self.bishops, sx, sy) {
-            Piece::Bishop
-        } else if get_bit(self.rooks, sx, sy) {
-            Piece::Rook
-        } else if get_bit(self.queens, sx, sy) {
-            Piece::Queen
-        } else if get_bit(self.kings, sx, sy) {
-            Piece::King
-        } else {
-            return Err(format!(
-                "internal error: {sx},{sy} is not empty, but is not a valid piece type"
-            ));
+        if let Some(outcome) = self.variant_outcome() {
+            return Err(format!("the game is over: {outcome:?}"));
+        }
+
+        // castling written as the king moving two squares
+        let to = match piece {
+            Piece::King => self.castling_rook(from, to, color).unwrap_or(to),
+            _ => to,
         };
 
-        let moves = grid_to_coords(bitboard_to_grid(piece_moves(*self, sx, sy)));
-        if !moves.contains(&(dx, dy)) {
+        let sy = from.rank().index();
+        let (dx, dy) = to.coords();
+
+        if !get_bit(piece_moves(*self, from), dx, dy) {
+            return Err(format!("the move {from} -> {to} is not a valid move"));
+        }
+
+        let castling = piece == Piece::King && get_bit(self.side(color), dx, dy);
+        let en_passant = piece == Piece::Pawn && get_bit(self.en_passant_targets, dx, dy);
+        let captured = match self.piece_at(to) {
+            Some((victim, victim_color)) if victim_color != color => Some(victim),
+            _ if en_passant => Some(Piece::Pawn),
+            _ => None,
+        };
+        let capture = captured.is_some();
+        let promotion = piece == Piece::Pawn && (dy == 7 || dy == 0);
+
+        if self.variant == Variant::Antichess && !capture && self.can_capture() {
             return Err(format!(
-                "the move {sx},{sy} -> {dx},{dy} is not a valid move"
+                "the move {from} -> {to} is not legal: a capture is forced"
+            ));
+        }
+        if promotion && promote.is_some_and(|piece| !self.promotions().contains(&piece)) {
+            return Err(format!(
+                "the move {from} -> {to} is not legal: pawns cannot promote to that piece"
+            ));
+        }
+        if castling && !self.castling_is_safe(from, to, color) {
+            return Err(format!(
+                "cannot castle as {}: would castle out of or through check",
+                if white { "white" } else { "black" }
             ));
         }
 
-        let mut test = *self;
-        test.whites_turn = !test.whites_turn;
-        test.white = unset_bit(test.white, sx, sy);
-        test.black = unset_bit(test.black, sx, sy);
-
-        test.unset(dx, dy);
-        test.unset(sx, sy);
+        // from here on the board changes, and an illegal move is unmade
+        if self.variant == Variant::Crazyhouse {
+            self.update_pockets(from, to, captured, promotion);
+        }
+        self.whites_turn = !self.whites_turn;
 
-        if piece == Piece::Pawn && get_bit(test.en_passant_targets, dx, dy) {
-            test.en_passant_targets = unset_bit(test.en_passant_targets, dx, dy);
+        self.unset_undoable(undo, to);
+        self.unset_undoable(undo, from);
 
-            // if you took an en passant target, the target has to be
-            // on a certain rank (dependent on color), and on the same file
-            // that you're moving to
-            test.unset(dx, if white { 4 } else { 3 });
+        if en_passant {
+            // the pawn taken en passant is beside the one taking it, on the
+            // file it moves to
+            self.unset_undoable(undo, Square::new(to.file(), from.rank()));
         }
 
-        if piece == Piece::King && white {
-            if (sx, sy, dx, dy) == (4, 0, 6, 0) {
-                if (test.white | test.black) & 0b01100000 != 0 {
-                    return Err("cannot short castle as white: pieces are in the way".to_string());
-                } else if black_captures(test) & 0b01110000 != 0 {
-                    return Err(
-                        "cannot short castle as white: would castle through check".to_string()
-                    );
-                } else {
-                    test.set(Piece::King, Color::White, dx, dy);
-                    test.unset(7, 0);
-                    test.set(Piece::Rook, Color::White, 5, 0);
-                }
-            } else if (sx, sy, dx, dy) == (4, 0, 2, 0) {
-                if (test.white | test.black) & 0b00001110 != 0 {
-                    return Err("cannot long castle as white: pieces are in the way".to_string());
-                } else if black_captures(test) & 0b00011100 != 0 {
-                    return Err(
-                        "cannot long castle as white: would castle through check".to_string()
-                    );
-                } else {
-                    test.set(Piece::King, Color::White, dx, dy);
-                    test.unset(0, 0);
-                    test.set(Piece::Rook, Color::White, 3, 0);
-                }
-            } else {
-                test.set(
-                    piece,
-                    if white { Color::White } else { Color::Black },
-                    dx,
-                    dy,
-                );
-            }
-        } else if piece == Piece::King && !white {
-            if (sx, sy, dx, dy) == (4, 7, 6, 7) {
-                if (test.white | test.black) & (0b01100000 << 56) != 0 {
-                    return Err("cannot short castle as black: pieces are in the way".to_string());
-                } else if white_captures(test) & (0b01110000 << 56) != 0 {
-                    return Err(
-                        "cannot short castle as black: would castle through check".to_string()
-                    );
-                } else {
-                    test.set(Piece::King, Color::Black, dx, dy);
-                    test.unset(7, 7);
-                    test.set(Piece::Rook, Color::Black, 5, 7);
-                }
-            } else if (sx, sy, dx, dy) == (4, 7, 2, 7) {
-                if (test.white | test.black) & (0b00001110 << 56) != 0 {
-                    return Err("cannot long castle as black: pieces are in the way".to_string());
-                } else if white_captures(test) & (0b00011100 << 56) != 0 {
-                    return Err(
-                        "cannot long castle as black: would castle through check".to_string()
-                    );
-                } else {
-                    test.set(Piece::King, Color::Black, dx, dy);
-                    test.unset(0, 7);
-                    test.set(Piece::Rook, Color::Black, 3, 7);
-                }
-            } else {
-                test.set(
-                    piece,
-                    if white { Color::White } else { Color::Black },
-                    dx,
-                    dy,
-                );
-            }
-        } else if piece == Piece::Pawn && (dy == 7 || dy == 0) {
-            test.set(
-                promote.unwrap_or(Piece::Queen),
-                if white { Color::White } else { Color::Black },
-                dx,
-                dy,
-            );
+        if castling {
+            let (king_to, rook_to) = castling::castled_squares(color, to.file() > from.file());
+            self.set_undoable(undo, Piece::King, color, king_to);
+            self.set_undoable(undo, Piece::Rook, color, rook_to);
+        } else if promotion {
+            self.set_undoable(undo, promote.unwrap_or(Piece::Queen), color, to);
         } else {
-            test.set(
-                piece,
-                if white { Color::White } else { Color::Black },
-                dx,
-                dy,
-            );
+            self.set_undoable(undo, piece, color, to);
         }
 
-        if white && test.check().0 {
-            return Err(format!(
-                "the move {sx},{sy} -> {dx},{dy} is not a legal move (puts white in check)"
-            ));
-        } else if !white && test.check().1 {
+        if self.variant == Variant::Atomic && capture {
+            self.explode(to, undo);
+        }
+
+        if !self.king_safe(color) {
+            self.unmake(undo);
             return Err(format!(
-                "the move {sx},{sy} -> {dx},{dy} is not a legal move (puts black in check)"
+                "the move {from} -> {to} is not a legal move (puts {} in check)",
+                if white { "white" } else { "black" }
             ));
         }
 
-        *self = test;
         self.en_passant_targets = 0;
 
+        if piece == Piece::Pawn || capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if !white {
+            self.fullmove_number += 1;
+        }
+
+        // a horde pawn moving two squares from the first rank can't be taken
+        // en passant
         if piece == Piece::Pawn {
-            if white && sy == 6 && dy == 4 {
-                self.en_passant_targets = set_bit(self.en_passant_targets, dx, 5);
-            } else if !white && sy == 1 && dy == 3 {
+            if white && sy == 1 && dy == 3 {
                 self.en_passant_targets = set_bit(self.en_passant_targets, dx, 2);
+            } else if !white && sy == 6 && dy == 4 {
+                self.en_passant_targets = set_bit(self.en_passant_targets, dx, 5);
             }
         } else if piece == Piece::King {
-            if white {
-                self.white_can_castle = (false, false);
-            } else {
-                self.black_can_castle = (false, false);
-            }
+            self.castling.clear(color);
         }
 
-        if (sx, sy) == (0, 0) || (dx, dy) == (0, 0) {
-            self.white_can_castle.0 = false;
-        } else if (sx, sy) == (7, 0) || (dx, dy) == (7, 0) {
-            self.white_can_castle.1 = false;
-        }
+        self.castling.touch(from);
+        self.castling.touch(to);
 
-        if (sx, sy) == (0, 7) || (dx, dy) == (0, 7) {
-            self.black_can_castle.0 = false;
-        } else if (sx, sy) == (7, 7) || (dx, dy) == (7, 7) {
-            self.black_can_castle.1 = false;
+        if self.variant == Variant::ThreeCheck && self.king_in_check(!color) {
+            self.checks[color as usize] += 1;
         }
 
         Ok(())
@@ -919,6 +1040,7 @@ impl Board {
         ))
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn from_uci(uci: String) -> Result<((u8, u8), (u8, u8)), String> {
         if uci.len() != 4 {
             return Err(format!(
@@ -977,9 +1099,226 @@ impl Board {
         ))
     }
 
+    /// `mv` in UCI notation. Castling is written as the king moving two
+    /// squares, except in chess960 where it is the king taking its rook.
+    pub fn uci_move(&self, mv: Move) -> String {
+        match self.piece_at(mv.from) {
+            Some((Piece::King, color)) if !self.chess960 && self.side(color) & mv.to.bit() != 0 => {
+                let (king_to, _) = castling::castled_squares(color, mv.to.file() > mv.from.file());
+                format!("{}{}", mv.from, king_to)
+            }
+            _ => mv.uci(),
+        }
+    }
+
+    /// Reads a move in UCI notation, such as `e2e4`, `e7e8q` or the drop
+    /// `N@f3`. Castling may be written either way in standard chess.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, String> {
+        if let Some((piece, to)) = uci.split_once('@') {
+            let piece = match piece.to_ascii_uppercase().as_str() {
+                "P" => Piece::Pawn,
+                "N" => Piece::Knight,
+                "B" => Piece::Bishop,
+                "R" => Piece::Rook,
+                "Q" => Piece::Queen,
+                _ => return Err(format!("invalid drop: {uci}")),
+            };
+            return Ok(Move::drop(piece, to.parse()?));
+        }
+
+        let (Some(from), Some(to)) = (uci.get(0..2), uci.get(2..4)) else {
+            return Err(format!("not a UCI move: {uci}"));
+        };
+        let (from, to) = (from.parse::<Square>()?, to.parse::<Square>()?);
+
+        let promote = match &uci[4..] {
+            "" => None,
+            "n" => Some(Piece::Knight),
+            "b" => Some(Piece::Bishop),
+            "r" => Some(Piece::Rook),
+            "q" => Some(Piece::Queen),
+            "k" => Some(Piece::King),
+            other => return Err(format!("invalid promotion: {other}")),
+        };
+
+        let to = match self.piece_at(from) {
+            Some((Piece::King, color)) => self.castling_rook(from, to, color).unwrap_or(to),
+            _ => to,
+        };
+
+        Ok(Move::new(from, to, promote))
+    }
+
     pub fn uci(&mut self, uci: String, promote: Option<Piece>) -> Result<(), String> {
         let ((sx, sy), (dx, dy)) = Self::from_uci(uci)?;
-        self.move_piece(sx, sy, dx, dy, promote)
+        self.move_piece_xy(sx, sy, dx, dy, promote)
+    }
+
+    pub fn pieces(&self, piece: Piece) -> u64 {
+        match piece {
+            Piece::Pawn => self.pawns,
+            Piece::Knight => self.knights,
+            Piece::Bishop => self.bishops,
+            Piece::Rook => self.rooks,
+            Piece::Queen => self.queens,
+            Piece::King => self.kings,
+        }
+    }
+
+    pub fn side(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    pub(crate) fn piece_on(&self, square: Square) -> Option<Piece> {
+        self.piece_at(square).map(|(piece, _)| piece)
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        if self.whites_turn {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    /// Whether the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        self.king_in_check(self.side_to_move())
+    }
+
+    /// Whether `mv` takes a piece, including en passant.
+    pub fn is_capture(&self, mv: Move) -> bool {
+        let to = mv.to.bit();
+        self.side(!self.side_to_move()) & to != 0
+            || (self.pawns & mv.from.bit() != 0 && self.en_passant_targets & to != 0)
+    }
+
+    /// Every move the side to move could make, without checking whether it
+    /// leaves their own king in check. Promotions are expanded to all four
+    /// pieces (five in antichess, with the king), and drops are included in
+    /// crazyhouse.
+    pub fn pseudo_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.generate(&mut moves, |_| true);
+        self.drop_moves(&mut moves);
+        moves
+    }
+
+    /// The captures and promotions among [`Board::pseudo_moves`].
+    pub fn pseudo_captures(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.generate(&mut moves, |tactical| tactical);
+        moves
+    }
+
+    /// The rest of [`Board::pseudo_moves`]: quiet moves, castling and drops.
+    pub fn pseudo_quiets(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.generate(&mut moves, |tactical| !tactical);
+        self.drop_moves(&mut moves);
+        moves
+    }
+
+    fn promotions(&self) -> &'static [Piece] {
+        match self.variant {
+            Variant::Antichess => &[
+                Piece::Queen,
+                Piece::Rook,
+                Piece::Bishop,
+                Piece::Knight,
+                Piece::King,
+            ],
+            _ => &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight],
+        }
+    }
+
+    fn is_promotion(&self, from: Square, to: Square) -> bool {
+        self.pawns & from.bit() != 0 && (to.rank() == Rank::R1 || to.rank() == Rank::R8)
+    }
+
+    // the moves off the board whose being a capture or promotion passes
+    // `wanted`
+    fn generate(&self, moves: &mut Vec<Move>, wanted: impl Fn(bool) -> bool) {
+        for from in Bitboard(self.side(self.side_to_move())) {
+            for to in Bitboard(piece_moves(*self, from)) {
+                let promotion = self.is_promotion(from, to);
+                if !wanted(promotion || self.is_capture(Move::new(from, to, None))) {
+                    continue;
+                }
+
+                if promotion {
+                    for piece in self.promotions() {
+                        moves.push(Move::new(from, to, Some(*piece)));
+                    }
+                } else {
+                    moves.push(Move::new(from, to, None));
+                }
+            }
+        }
+    }
+
+    /// Whether `mv` is among [`Board::pseudo_moves`], worked out without
+    /// generating them: for checking a move from a transposition table or a
+    /// killer slot before trying it.
+    pub fn is_pseudo_legal(&self, mv: Move) -> bool {
+        if let Some(piece) = mv.drop {
+            return self.variant == Variant::Crazyhouse
+                && mv.promote.is_none()
+                && self.pocket(self.side_to_move()).count(piece) > 0
+                && self.drop_targets(piece) & mv.to.bit() != 0;
+        }
+
+        if self.side(self.side_to_move()) & mv.from.bit() == 0
+            || piece_moves(*self, mv.from) & mv.to.bit() == 0
+        {
+            return false;
+        }
+
+        match mv.promote {
+            Some(piece) => self.is_promotion(mv.from, mv.to) && self.promotions().contains(&piece),
+            None => !self.is_promotion(mv.from, mv.to),
+        }
+    }
+
+    /// Returns the board after `mv`, or `None` if the move is illegal.
+    pub fn play(&self, mv: Move) -> Option<Board> {
+        let mut board = *self;
+        match mv.drop {
+            Some(piece) => board.drop_piece(piece, mv.to),
+            None => board.move_piece(mv.from, mv.to, mv.promote),
+        }
+        .ok()
+        .map(|_| board)
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.pseudo_moves()
+            .into_iter()
+            .filter(|mv| self.play(*mv).is_some())
+            .collect()
+    }
+
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mut nodes = 0;
+        for mv in self.pseudo_moves() {
+            if let Some(board) = self.play(mv) {
+                nodes += if depth == 1 {
+                    1
+                } else {
+                    board.perft(depth - 1)
+                };
+            }
+        }
+
+        nodes
     }
 }
 
@@ -1086,31 +1425,31 @@ mod tests {
     #[test]
     fn test_piece_moves() {
         let mut board = Board::new();
-        board.set(Piece::Queen, Color::White, 3, 4);
-        board.set(Piece::Rook, Color::White, 6, 5);
-        board.set(Piece::Knight, Color::White, 2, 3);
+        board.set(Piece::Queen, Color::White, Square::D5);
+        board.set(Piece::Rook, Color::White, Square::G6);
+        board.set(Piece::Knight, Color::White, Square::C4);
 
-        board.set(Piece::Queen, Color::Black, 6, 7);
-        board.set(Piece::Rook, Color::Black, 4, 5);
-        board.set(Piece::Knight, Color::Black, 5, 3);
+        board.set(Piece::Queen, Color::Black, Square::G8);
+        board.set(Piece::Rook, Color::Black, Square::E6);
+        board.set(Piece::Knight, Color::Black, Square::F4);
 
         print_board(board);
 
         println!("\n\n white possible queen moves");
-        print_bitboard(piece_moves(board, 3, 4));
+        print_bitboard(piece_moves(board, Square::D5));
 
         println!("\n\n black possible knight moves");
-        print_bitboard(piece_moves(board, 5, 3));
+        print_bitboard(piece_moves(board, Square::F4));
 
         println!("\n\n black possible rook moves");
-        print_bitboard(piece_moves(board, 4, 5));
+        print_bitboard(piece_moves(board, Square::E6));
     }
 
     #[test]
     fn test_fen() {
         let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
-        let board = Board::try_from(fen).unwrap();
+        let board = Board::from(fen);
 
         println!("\n\n == default == ");
         print_board(Board::default());
@@ -1120,16 +1459,181 @@ mod tests {
 
         let fen = "r2qnrk1/3nbppp/p2pb3/4p1P1/1p2PP2/1N2B3/PPPQN2P/2KR1B1R b - - 0 14";
 
-        let board = Board::try_from(fen).unwrap();
+        let board = Board::from(fen);
 
         println!("\n\n == board 1 == ");
         print_board(board);
 
         let fen = "r1b1k1nr/p2p1pNp/n2B4/1p1NP2P/6P1/3P1Q2/P1P1K3/q5b1 b - - 0 1";
 
-        let board = Board::try_from(fen).unwrap();
+        let board = Board::from(fen);
 
         println!("\n\n == board 2 == ");
         print_board(board);
     }
+
+    #[test]
+    fn test_move_generator_fixes() {
+        // shifts of more than one file drop what falls off the board instead
+        // of wrapping it onto the next rank
+        assert_eq!(bitboard_shr(1, 3), 1 << 3);
+        assert_eq!(bitboard_shr(1 << 6, 2), 0);
+        assert_eq!(bitboard_shl(1 << 9, 2), 0);
+        assert_eq!(bitboard_shl(1 << 7, 8), 0);
+
+        // double pushes leave an en passant target behind the pawn
+        let mut board = Board::from("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1");
+        board.uci("e2e4".to_string(), None).unwrap();
+        assert_eq!(board.en_passant_targets, 1 << 20);
+        board.uci("e7e5".to_string(), None).unwrap();
+        assert_eq!(board.en_passant_targets, 1 << 44);
+
+        // moving the h1 rook only costs the kingside right
+        let mut board = Board::from("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        for uci in ["h1h2", "e8d8", "h2h1", "d8e8"] {
+            board.uci(uci.to_string(), None).unwrap();
+        }
+        let mut kingside = board;
+        assert!(kingside.uci("e1g1".to_string(), None).is_err());
+        assert!(board.uci("e1c1".to_string(), None).is_ok());
+
+        // castling needs an empty square for the king, and is no way out of
+        // check
+        let mut board = Board::from("4k3/8/8/8/8/8/8/4K1NR w K - 0 1");
+        assert!(board.uci("e1g1".to_string(), None).is_err());
+        let board = Board::from("3rkr1r/3p1p2/8/8/8/8/8/K3R3 b k - 0 1");
+        assert_eq!(board.mate(), (false, true));
+    }
+
+    #[test]
+    fn test_perft() {
+        assert_eq!(Board::default().perft(3), 8902);
+
+        let kiwipete =
+            Board::from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(kiwipete.perft(2), 2039);
+
+        let endgame = Board::from("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+        assert_eq!(endgame.perft(3), 2812);
+
+        let promotions = Board::from("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
+        assert_eq!(promotions.perft(2), 1486);
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let board = Board::default();
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 20);
+        assert!(moves.contains(&Move::new(Square::E2, Square::E4, None)));
+
+        // the king may not castle through the knight's attack on f1
+        let board = Board::from("4k3/8/8/8/8/4n3/8/4K2R w K - 0 1");
+        assert!(!board
+            .legal_moves()
+            .contains(&Move::new(Square::E1, Square::G1, None)));
+    }
+
+    #[test]
+    fn test_captures_match_shifts() {
+        // the table based attack sets against the shift based ones
+        let reference = |board: Board, color: Color| {
+            let mine = board.side(color);
+            let occupied = board.white | board.black;
+            let pawns = match color {
+                Color::White => pawns_capture_down(board.pawns & mine, mine),
+                Color::Black => pawns_capture_up(board.pawns & mine, mine),
+            };
+            pawns
+                | knight_moves(board.knights & mine, mine)
+                | (slide_diag(board.bishops & mine, occupied)
+                    | slide_horiz(board.rooks & mine, occupied)
+                    | slide_all(board.queens & mine, occupied))
+                    & !mine
+                | king_moves(board.kings & mine, mine)
+        };
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let board = Board::from(fen);
+            assert_eq!(
+                white_captures(board),
+                reference(board, Color::White),
+                "{fen}"
+            );
+            assert_eq!(
+                black_captures(board),
+                reference(board, Color::Black),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pseudo_legal() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3",
+        ] {
+            let board = Board::from(fen);
+            let moves = board.pseudo_moves();
+            assert!(moves.iter().all(|mv| board.is_pseudo_legal(*mv)), "{fen}");
+
+            let mut split = board.pseudo_captures();
+            assert!(split.iter().all(|mv| movepick::is_tactical(&board, *mv)));
+            split.extend(board.pseudo_quiets());
+            assert_eq!(split.len(), moves.len());
+            assert!(split.iter().all(|mv| moves.contains(mv)));
+        }
+
+        let board = Board::default();
+        assert!(!board.is_pseudo_legal(Move::new(Square::E2, Square::E5, None)));
+        assert!(!board.is_pseudo_legal(Move::new(Square::E7, Square::E5, None)));
+        assert!(!board.is_pseudo_legal(Move::new(Square::E2, Square::E4, Some(Piece::Queen))));
+        assert!(!board.is_pseudo_legal(Move::drop(Piece::Knight, Square::E4)));
+
+        let board = Board::from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(!board.is_pseudo_legal(Move::new(Square::B7, Square::B8, None)));
+        assert!(!board.is_pseudo_legal(Move::new(Square::B7, Square::B8, Some(Piece::King))));
+        assert!(board.is_pseudo_legal(Move::new(Square::B7, Square::B8, Some(Piece::Knight))));
+    }
+
+    #[test]
+    fn test_piece_at() {
+        let board = Board::default();
+        assert_eq!(
+            board.piece_at(Square::E1),
+            Some((Piece::King, Color::White))
+        );
+        assert_eq!(
+            board.piece_at(Square::D8),
+            Some((Piece::Queen, Color::Black))
+        );
+        assert_eq!(board.piece_at(Square::E4), None);
+        assert!(Board::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1") == board);
+
+        // captures, castling, en passant and promotions all keep the mailbox
+        // in step with the bitboards
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let root = Board::from(fen);
+            for mv in root.legal_moves() {
+                let board = root.play(mv).unwrap();
+                for reply in board.legal_moves() {
+                    let board = board.play(reply).unwrap();
+                    for square in Square::all() {
+                        assert_eq!(board.piece_at(square), board.find_piece(square));
+                    }
+                }
+            }
+        }
+    }
 }
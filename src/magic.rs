@@ -0,0 +1,217 @@
+// sliding piece attacks by table lookup.
+//
+// each square gets a mask of the squares that can block its rays (edges
+// excluded, since a piece there can't block anything further). the blockers
+// inside the mask are turned into a table index either by PEXT, when compiled
+// for a cpu with BMI2, or by multiplying with a magic number that maps every
+// blocker pattern to a distinct (or identically attacked) slot. the tables are
+// built on first use from the `slide_*` functions, which stay around as the
+// reference implementation.
+
+use std::sync::OnceLock;
+
+use crate::{bitxy, slide_diag, slide_horiz, Bitboard, Square};
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    #[inline]
+    fn index(&self, occupied: u64) -> usize {
+        #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+        {
+            // SAFETY: only compiled in when the target has BMI2
+            self.offset + unsafe { std::arch::x86_64::_pext_u64(occupied, self.mask) } as usize
+        }
+
+        #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+        {
+            self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+        }
+    }
+}
+
+struct Tables {
+    bishops: [Magic; 64],
+    rooks: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        let mut attacks = Vec::new();
+        let bishops = build(&mut attacks, slide_diag);
+        let rooks = build(&mut attacks, slide_horiz);
+
+        Tables {
+            bishops,
+            rooks,
+            attacks,
+        }
+    })
+}
+
+// xorshift64*, seeded per rank with seeds known to find magics quickly
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(2685821657736338717)
+    }
+
+    // magics work best with few bits set
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+const SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+
+fn build(attacks: &mut Vec<u64>, slide: fn(u64, u64) -> u64) -> [Magic; 64] {
+    let mut magics = [Magic::default(); 64];
+
+    let mut occupancies = Vec::with_capacity(4096);
+    let mut reference = Vec::with_capacity(4096);
+    let mut epochs = vec![0u32; 4096];
+    let mut epoch = 0;
+
+    for (square, magic) in magics.iter_mut().enumerate() {
+        let (x, y) = (square as u8 & 7, square as u8 >> 3);
+        let bit = bitxy(x, y);
+
+        let rank_edges = 0xFF | 0xFF << 56;
+        let file_edges = 0x0101010101010101 | 0x8080808080808080;
+        let edges = (rank_edges & !(0xFF << (y * 8))) | (file_edges & !(0x0101010101010101 << x));
+
+        magic.mask = slide(bit, 0) & !edges;
+        magic.shift = 64 - magic.mask.count_ones();
+        magic.offset = attacks.len();
+
+        // every subset of the mask (carry-rippler), and what it lets through
+        occupancies.clear();
+        reference.clear();
+        let mut subset = 0u64;
+        loop {
+            occupancies.push(subset);
+            reference.push(slide(bit, subset));
+
+            subset = subset.wrapping_sub(magic.mask) & magic.mask;
+            if subset == 0 {
+                break;
+            }
+        }
+
+        attacks.resize(magic.offset + occupancies.len(), 0);
+
+        if cfg!(all(target_arch = "x86_64", target_feature = "bmi2")) {
+            for (occupied, moves) in occupancies.iter().zip(&reference) {
+                attacks[magic.index(*occupied)] = *moves;
+            }
+            continue;
+        }
+
+        let mut rng = Rng(SEEDS[y as usize]);
+        'search: loop {
+            magic.magic = loop {
+                let candidate = rng.sparse();
+                if (candidate.wrapping_mul(magic.mask) >> 56).count_ones() >= 6 {
+                    break candidate;
+                }
+            };
+
+            // `epochs` marks which slots this attempt has written, so the
+            // table doesn't need clearing between attempts
+            epoch += 1;
+            for (occupied, moves) in occupancies.iter().zip(&reference) {
+                let index = magic.index(*occupied);
+                let slot = index - magic.offset;
+
+                if epochs[slot] < epoch {
+                    epochs[slot] = epoch;
+                    attacks[index] = *moves;
+                } else if attacks[index] != *moves {
+                    continue 'search;
+                }
+            }
+
+            break;
+        }
+    }
+
+    magics
+}
+
+/// Squares a bishop on `square` attacks, stopping at (and including) the
+/// first piece in `occupied` along each diagonal.
+#[inline]
+pub fn bishop_attacks(square: Square, occupied: u64) -> u64 {
+    let tables = tables();
+    tables.attacks[tables.bishops[square.index() as usize].index(occupied)]
+}
+
+/// Squares a rook on `square` attacks, stopping at (and including) the first
+/// piece in `occupied` along each rank and file.
+#[inline]
+pub fn rook_attacks(square: Square, occupied: u64) -> u64 {
+    let tables = tables();
+    tables.attacks[tables.rooks[square.index() as usize].index(occupied)]
+}
+
+#[inline]
+pub fn queen_attacks(square: Square, occupied: u64) -> u64 {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/// The union of `attacks` from every square in `bb`.
+#[inline]
+pub fn attacks_from(bb: u64, occupied: u64, attacks: fn(Square, u64) -> u64) -> u64 {
+    Bitboard(bb)
+        .iter()
+        .fold(0, |all, square| all | attacks(square, occupied))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::magic::*;
+    use crate::slide_all;
+
+    #[test]
+    fn test_matches_slide() {
+        let mut rng = Rng(0x5EED);
+
+        for square in Square::all() {
+            let bit = square.bit();
+
+            for _ in 0..200 {
+                let occupied = rng.next() & rng.next();
+
+                assert_eq!(bishop_attacks(square, occupied), slide_diag(bit, occupied));
+                assert_eq!(rook_attacks(square, occupied), slide_horiz(bit, occupied));
+                assert_eq!(queen_attacks(square, occupied), slide_all(bit, occupied));
+            }
+
+            assert_eq!(rook_attacks(square, 0), slide_horiz(bit, 0));
+            assert_eq!(bishop_attacks(square, !0), slide_diag(bit, !0));
+        }
+    }
+
+    #[test]
+    fn test_attacks_from() {
+        let rooks = Square::A1.bit() | Square::H8.bit();
+        let occupied = rooks | Square::A5.bit();
+        assert_eq!(
+            attacks_from(rooks, occupied, rook_attacks),
+            slide_horiz(Square::A1.bit(), occupied) | slide_horiz(Square::H8.bit(), occupied)
+        );
+    }
+}
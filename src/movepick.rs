@@ -0,0 +1,377 @@
+// staged move ordering for the search.
+//
+// moves come out in this order, each stage only generated and sorted once it
+// is reached: the hash move, captures that don't lose material by static exchange (most
+// valuable victim, least valuable attacker first), killer moves and the
+// counter move, the remaining quiet moves by history score, and finally the
+// captures that lose material.
+
+use crate::eval::piece_value;
+use crate::search::MAX_PLY;
+use crate::{Board, Color, Move, Piece, Square};
+
+const MAX_HISTORY: i32 = 16_384;
+
+fn square(square: Square) -> usize {
+    square.index() as usize
+}
+
+/// Killer, history and counter-move tables, learned from beta cutoffs by
+/// quiet moves. Each search thread keeps its own.
+pub struct Heuristics {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    counters: Box<[[Option<Move>; 64]; 64]>,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heuristics {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY as usize + 1],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            counters: Box::new([[None; 64]; 64]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers.get(ply).copied().unwrap_or([None; 2])
+    }
+
+    pub fn history(&self, color: Color, mv: Move) -> i32 {
+        self.history[color as usize][square(mv.from)][square(mv.to)]
+    }
+
+    /// The move that last refuted `previous`.
+    pub fn counter(&self, previous: Option<Move>) -> Option<Move> {
+        previous.and_then(|prev| self.counters[square(prev.from)][square(prev.to)])
+    }
+
+    fn adjust_history(&mut self, color: Color, mv: Move, bonus: i32) {
+        let entry = &mut self.history[color as usize][square(mv.from)][square(mv.to)];
+        // the further from zero, the smaller the change, so scores saturate
+        // instead of overflowing
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    /// Records that the quiet move `mv` caused a beta cutoff, after every move
+    /// in `tried` failed to.
+    pub fn update(
+        &mut self,
+        color: Color,
+        mv: Move,
+        ply: usize,
+        depth: i32,
+        previous: Option<Move>,
+        tried: &[Move],
+    ) {
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0] != Some(mv) {
+                killers[1] = killers[0];
+                killers[0] = Some(mv);
+            }
+        }
+
+        let bonus = (depth * depth).min(MAX_HISTORY);
+        self.adjust_history(color, mv, bonus);
+        for quiet in tried {
+            if *quiet != mv {
+                self.adjust_history(color, *quiet, -bonus);
+            }
+        }
+
+        if let Some(prev) = previous {
+            self.counters[square(prev.from)][square(prev.to)] = Some(mv);
+        }
+    }
+}
+
+/// Whether `mv` is a capture or a promotion.
+pub fn is_tactical(board: &Board, mv: Move) -> bool {
+    mv.promote.is_some() || board.is_capture(mv)
+}
+
+/// Most valuable victim, least valuable attacker.
+pub fn mvv_lva(board: &Board, mv: Move) -> i32 {
+    let victim = board.piece_on(mv.to).unwrap_or(Piece::Pawn);
+    let attacker = board.piece_on(mv.from).unwrap_or(Piece::Pawn);
+
+    piece_value(victim) * 10 - piece_value(attacker) / 10 + mv.promote.map(piece_value).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Yields pseudo-legal moves in staged order. The caller still has to check
+/// that each move is legal.
+///
+/// The heuristics are passed to every `next_move` call rather than held, so
+/// the search can update them while it walks the moves.
+pub struct MovePicker<'a> {
+    board: &'a Board,
+    stage: Stage,
+    captures_only: bool,
+
+    hash_move: Option<Move>,
+    refutations: [Option<Move>; 3],
+    refutation: usize,
+
+    captures: Vec<(Move, i32)>,
+    bad_captures: Vec<(Move, i32)>,
+    quiets: Vec<(Move, i32)>,
+}
+
+impl<'a> MovePicker<'a> {
+    pub fn new(
+        board: &'a Board,
+        heuristics: &Heuristics,
+        hash_move: Option<Move>,
+        ply: usize,
+        previous: Option<Move>,
+    ) -> Self {
+        let [first, second] = heuristics.killers(ply);
+        let counter = heuristics.counter(previous);
+
+        Self {
+            board,
+            stage: Stage::HashMove,
+            captures_only: false,
+            hash_move,
+            refutations: [first, second, counter],
+            refutation: 0,
+            captures: Vec::new(),
+            bad_captures: Vec::new(),
+            quiets: Vec::new(),
+        }
+    }
+
+    /// Only captures and promotions, for quiescence search.
+    pub fn captures(board: &'a Board) -> Self {
+        Self {
+            board,
+            stage: Stage::HashMove,
+            captures_only: true,
+            hash_move: None,
+            refutations: [None; 3],
+            refutation: 0,
+            captures: Vec::new(),
+            bad_captures: Vec::new(),
+            quiets: Vec::new(),
+        }
+    }
+
+    fn already_tried(&self, mv: Move) -> bool {
+        Some(mv) == self.hash_move || self.refutations[..self.refutation].contains(&Some(mv))
+    }
+
+    // a selection sort, one move at a time: cheaper than sorting the whole
+    // list when a cutoff comes early
+    fn pop_best(moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+        let best = moves
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, (_, score))| (*score, std::cmp::Reverse(*i)))?
+            .0;
+
+        Some(moves.remove(best).0)
+    }
+
+    pub fn next_move(&mut self, heuristics: &Heuristics) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+
+                    // a cutoff on the hash move saves generating anything
+                    if let Some(mv) = self.hash_move {
+                        if self.board.is_pseudo_legal(mv)
+                            && (!self.captures_only || is_tactical(self.board, mv))
+                        {
+                            return Some(mv);
+                        }
+                        self.hash_move = None;
+                    }
+                }
+
+                Stage::GenerateCaptures => {
+                    self.stage = Stage::GoodCaptures;
+
+                    for mv in self.board.pseudo_captures() {
+                        if Some(mv) == self.hash_move {
+                            continue;
+                        }
+
+                        let score = mvv_lva(self.board, mv);
+                        if self.board.see_ge(mv, 0) {
+                            self.captures.push((mv, score));
+                        } else {
+                            self.bad_captures.push((mv, score));
+                        }
+                    }
+                }
+
+                Stage::GoodCaptures => match Self::pop_best(&mut self.captures) {
+                    Some(mv) => return Some(mv),
+                    None => {
+                        self.stage = if self.captures_only {
+                            Stage::BadCaptures
+                        } else {
+                            Stage::Killers
+                        }
+                    }
+                },
+
+                Stage::Killers => {
+                    if self.refutation >= self.refutations.len() {
+                        self.stage = Stage::GenerateQuiets;
+                        continue;
+                    }
+
+                    let candidate = self.refutations[self.refutation];
+                    let duplicate = candidate.is_some()
+                        && self.refutations[..self.refutation].contains(&candidate);
+                    self.refutation += 1;
+
+                    if let Some(mv) = candidate {
+                        if !duplicate
+                            && Some(mv) != self.hash_move
+                            && !is_tactical(self.board, mv)
+                            && self.board.is_pseudo_legal(mv)
+                        {
+                            return Some(mv);
+                        }
+                    }
+                }
+
+                Stage::GenerateQuiets => {
+                    self.stage = Stage::Quiets;
+
+                    let color = self.board.side_to_move();
+                    for mv in self.board.pseudo_quiets() {
+                        if !self.already_tried(mv) {
+                            self.quiets.push((mv, heuristics.history(color, mv)));
+                        }
+                    }
+                }
+
+                Stage::Quiets => match Self::pop_best(&mut self.quiets) {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::BadCaptures,
+                },
+
+                Stage::BadCaptures => match Self::pop_best(&mut self.bad_captures) {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::movepick::*;
+
+    #[test]
+    fn test_stages() {
+        // white can take the queen with a pawn, the rook with a queen (which
+        // the pawn on b6 defends) or play something quiet
+        let board = Board::from("4k3/8/1pr5/3q4/4P3/8/2Q5/4K3 w - - 0 1");
+        let mut heuristics = Heuristics::new();
+
+        let killer = Move::new(Square::E1, Square::D1, None);
+        heuristics.update(Color::White, killer, 2, 4, None, &[]);
+
+        let hash = Move::new(Square::C2, Square::C3, None);
+        let mut picker = MovePicker::new(&board, &heuristics, Some(hash), 2, None);
+        let mut moves = Vec::new();
+        while let Some(mv) = picker.next_move(&heuristics) {
+            moves.push(mv);
+        }
+
+        assert_eq!(moves[0], hash);
+        assert_eq!(moves[1], Move::new(Square::E4, Square::D5, None));
+        assert_eq!(moves[2], killer);
+        assert_eq!(
+            *moves.last().unwrap(),
+            Move::new(Square::C2, Square::C6, None)
+        );
+
+        // every move comes out exactly once
+        let mut sorted = moves.clone();
+        sorted.sort_by_key(|mv| (mv.from, mv.to));
+        sorted.dedup();
+        assert_eq!(sorted.len(), moves.len());
+        assert_eq!(moves.len(), board.pseudo_moves().len());
+    }
+
+    #[test]
+    fn test_captures_only() {
+        let board = Board::from("4k3/8/1pr5/3q4/4P3/8/2Q5/4K3 w - - 0 1");
+        let heuristics = Heuristics::new();
+
+        let mut picker = MovePicker::captures(&board);
+        let mut moves = Vec::new();
+        while let Some(mv) = picker.next_move(&heuristics) {
+            moves.push(mv);
+        }
+        assert_eq!(
+            moves,
+            vec![
+                Move::new(Square::E4, Square::D5, None),
+                Move::new(Square::C2, Square::C6, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_hash_move() {
+        // a hash move from another position is skipped, not played
+        let board = Board::from("4k3/8/1pr5/3q4/4P3/8/2Q5/4K3 w - - 0 1");
+        let heuristics = Heuristics::new();
+        let hash = Move::new(Square::E2, Square::E4, None);
+
+        let mut picker = MovePicker::new(&board, &heuristics, Some(hash), 0, None);
+        assert_eq!(
+            picker.next_move(&heuristics),
+            Some(Move::new(Square::E4, Square::D5, None))
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let mut heuristics = Heuristics::new();
+        let good = Move::new(Square::G1, Square::F3, None);
+        let bad = Move::new(Square::B1, Square::A3, None);
+
+        for _ in 0..1000 {
+            heuristics.update(Color::White, good, 0, 10, Some(bad), &[bad, good]);
+        }
+
+        assert!(heuristics.history(Color::White, good) > 0);
+        assert!(heuristics.history(Color::White, good) <= MAX_HISTORY);
+        assert!(heuristics.history(Color::White, bad) < 0);
+        assert_eq!(heuristics.history(Color::Black, good), 0);
+        assert_eq!(heuristics.counter(Some(bad)), Some(good));
+    }
+}
@@ -0,0 +1,471 @@
+// chess problems: a position and a stipulation such as "white to play and
+// mate in two", solved by trying every legal move. the solution keeps every
+// move that works rather than stopping at the first, so a problem with more
+// than one key (a cook) shows up as more than one.
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::{Board, Color, Move, Piece, Square};
+
+/// What the side to move has to bring about, and in how many of its moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stipulation {
+    /// `#n`: mate the other side, whatever it does.
+    Mate(u32),
+    /// `h#n`: get mated, with both sides working together. The mate comes
+    /// on the other side's `n`th move, no sooner.
+    Helpmate(u32),
+    /// `s#n`: force the other side to mate, whatever it does.
+    Selfmate(u32),
+}
+
+impl Stipulation {
+    pub fn moves(self) -> u32 {
+        match self {
+            Stipulation::Mate(n) | Stipulation::Helpmate(n) | Stipulation::Selfmate(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for Stipulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stipulation::Mate(n) => write!(f, "#{n}"),
+            Stipulation::Helpmate(n) => write!(f, "h#{n}"),
+            Stipulation::Selfmate(n) => write!(f, "s#{n}"),
+        }
+    }
+}
+
+impl FromStr for Stipulation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, n) = s
+            .split_once('#')
+            .ok_or(format!("{s} doesn't have a '#' before the moves"))?;
+        let n = n
+            .parse()
+            .map_err(|_| format!("{n} is not a number of moves"))?;
+
+        match kind {
+            "" => Ok(Stipulation::Mate(n)),
+            "h" => Ok(Stipulation::Helpmate(n)),
+            "s" => Ok(Stipulation::Selfmate(n)),
+            _ => Err(format!("{kind}# is not a stipulation")),
+        }
+    }
+}
+
+/// A move and the moves that answer it, each with what follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variation {
+    pub mv: Move,
+    pub replies: Vec<Variation>,
+}
+
+/// Every first move that meets the stipulation, with the play after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub keys: Vec<Variation>,
+}
+
+impl Solution {
+    /// Whether any move meets the stipulation.
+    pub fn is_solved(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// A sound problem has exactly one key. Any further ones are cooks.
+    pub fn cooks(&self) -> &[Variation] {
+        self.keys.get(1..).unwrap_or(&[])
+    }
+
+    /// Every path through the tree, from a key to the end of the play. For
+    /// a helpmate these are the solutions.
+    pub fn lines(&self) -> Vec<Vec<Move>> {
+        fn walk(variations: &[Variation], line: &mut Vec<Move>, lines: &mut Vec<Vec<Move>>) {
+            for variation in variations {
+                line.push(variation.mv);
+                if variation.replies.is_empty() {
+                    lines.push(line.clone());
+                } else {
+                    walk(&variation.replies, line, lines);
+                }
+                line.pop();
+            }
+        }
+
+        let mut lines = Vec::new();
+        walk(&self.keys, &mut Vec::new(), &mut lines);
+        lines
+    }
+
+    /// The solution tree in algebraic notation, a move per line with its
+    /// answers indented below it and key moves marked `!`.
+    pub fn text(&self, board: &Board) -> String {
+        let mut text = String::new();
+        for key in &self.keys {
+            write_variation(&mut text, board, key, 0, true);
+        }
+        text
+    }
+}
+
+fn write_variation(
+    text: &mut String,
+    board: &Board,
+    variation: &Variation,
+    depth: usize,
+    key: bool,
+) {
+    let number = if board.whites_turn {
+        format!("{}.", board.fullmove_number)
+    } else {
+        format!("{}...", board.fullmove_number)
+    };
+    let mark = if key { "!" } else { "" };
+    let _ = writeln!(
+        text,
+        "{}{number}{}{mark}",
+        "  ".repeat(depth),
+        board.san(variation.mv)
+    );
+
+    if let Some(next) = board.play(variation.mv) {
+        for reply in &variation.replies {
+            write_variation(text, &next, reply, depth + 1, false);
+        }
+    }
+}
+
+/// A change to the diagram that makes a twin, as in "b) wKe1->e2".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Twin {
+    /// Moves the piece on the first square to the second, replacing
+    /// whatever was there.
+    Move(Square, Square),
+    Remove(Square),
+    Add(Square, Piece, Color),
+    /// Swaps what is on two squares.
+    Exchange(Square, Square),
+    /// The same position with another stipulation.
+    Stipulation(Stipulation),
+    /// The same stipulation with the other side to move.
+    Duplex,
+}
+
+impl Twin {
+    /// The twin of a problem, if it is still a legal position.
+    pub fn apply(
+        self,
+        board: &Board,
+        stipulation: Stipulation,
+    ) -> Result<(Board, Stipulation), String> {
+        let mut board = *board;
+        let mut stipulation = stipulation;
+
+        let occupied = |board: &Board, square: Square| {
+            board
+                .piece_at(square)
+                .ok_or(format!("there is no piece on {square}"))
+        };
+
+        match self {
+            Twin::Move(from, to) => {
+                let (piece, color) = occupied(&board, from)?;
+                board.unset(from);
+                board.set(piece, color, to);
+            }
+            Twin::Remove(square) => {
+                occupied(&board, square)?;
+                board.unset(square);
+            }
+            Twin::Add(square, piece, color) => board.set(piece, color, square),
+            Twin::Exchange(a, b) => {
+                let (piece_a, color_a) = occupied(&board, a)?;
+                let (piece_b, color_b) = occupied(&board, b)?;
+                board.set(piece_a, color_a, b);
+                board.set(piece_b, color_b, a);
+            }
+            Twin::Stipulation(new) => stipulation = new,
+            Twin::Duplex => {
+                board.whites_turn = !board.whites_turn;
+                board.en_passant_targets = 0;
+            }
+        }
+
+        board.validate().map_err(|errors| {
+            let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            errors.join(", ")
+        })?;
+
+        Ok((board, stipulation))
+    }
+}
+
+// the side to move has been mated
+fn is_mate(board: &Board) -> bool {
+    board.in_check() && board.legal_moves().is_empty()
+}
+
+fn children(board: &Board) -> impl Iterator<Item = (Move, Board)> + '_ {
+    board
+        .legal_moves()
+        .into_iter()
+        .filter_map(|mv| board.play(mv).map(|child| (mv, child)))
+}
+
+// whether the side to move can force the stipulation, a mate or a selfmate,
+// in at most `n` of its moves
+fn forces(board: &Board, selfmate: bool, n: u32) -> bool {
+    children(board).any(|(_, child)| is_forced(&child, selfmate, n))
+}
+
+// whether the defence, to move, loses whatever it does, the attack having
+// `n - 1` more moves. a selfmate is already won when every defence mates,
+// and a defence that mates early helps the attack just as well.
+fn is_forced(board: &Board, selfmate: bool, n: u32) -> bool {
+    if !selfmate {
+        if n == 1 {
+            return is_mate(board);
+        }
+        if board.legal_moves().is_empty() {
+            return board.in_check();
+        }
+    }
+
+    let mut replies = children(board).peekable();
+    if replies.peek().is_none() {
+        return false;
+    }
+
+    replies.all(|(_, child)| {
+        if selfmate && is_mate(&child) {
+            true
+        } else {
+            n > 1 && forces(&child, selfmate, n - 1)
+        }
+    })
+}
+
+fn forcing_moves(board: &Board, selfmate: bool, n: u32) -> Vec<Variation> {
+    let mut variations = Vec::new();
+    for (mv, child) in children(board) {
+        if !is_forced(&child, selfmate, n) {
+            continue;
+        }
+
+        // a defender that was mated has no replies, one that mated ends it
+        let replies = children(&child)
+            .map(|(reply, next)| Variation {
+                mv: reply,
+                replies: if is_mate(&next) {
+                    Vec::new()
+                } else {
+                    forcing_moves(&next, selfmate, n - 1)
+                },
+            })
+            .collect();
+
+        variations.push(Variation { mv, replies });
+    }
+
+    variations
+}
+
+// every way for the side to move to get mated in exactly `n` moves each
+fn helping_moves(board: &Board, n: u32) -> Vec<Variation> {
+    let mut variations = Vec::new();
+    for (mv, child) in children(board) {
+        let mut replies = Vec::new();
+        for (reply, next) in children(&child) {
+            if n == 1 {
+                if is_mate(&next) {
+                    replies.push(Variation {
+                        mv: reply,
+                        replies: Vec::new(),
+                    });
+                }
+            } else {
+                let further = helping_moves(&next, n - 1);
+                if !further.is_empty() {
+                    replies.push(Variation {
+                        mv: reply,
+                        replies: further,
+                    });
+                }
+            }
+        }
+
+        if !replies.is_empty() {
+            variations.push(Variation { mv, replies });
+        }
+    }
+
+    variations
+}
+
+/// Solves the problem by trying every legal move. For a mate or selfmate
+/// each key comes with every defence and, for each, every move that still
+/// works in time. A helpmate's tree holds every solution.
+pub fn solve(board: &Board, stipulation: Stipulation) -> Solution {
+    let keys = match stipulation {
+        Stipulation::Mate(0) | Stipulation::Helpmate(0) | Stipulation::Selfmate(0) => Vec::new(),
+        Stipulation::Mate(n) => forcing_moves(board, false, n),
+        Stipulation::Selfmate(n) => forcing_moves(board, true, n),
+        Stipulation::Helpmate(n) => helping_moves(board, n),
+    };
+
+    Solution { keys }
+}
+
+/// Solves the diagram and then each twin of it, in order. Every twin is
+/// made from the diagram, not from the twin before it.
+pub fn solve_twins(
+    board: &Board,
+    stipulation: Stipulation,
+    twins: &[Twin],
+) -> Result<Vec<Solution>, String> {
+    let mut solutions = vec![solve(board, stipulation)];
+    for twin in twins {
+        let (board, stipulation) = twin.apply(board, stipulation)?;
+        solutions.push(solve(&board, stipulation));
+    }
+
+    Ok(solutions)
+}
+
+/// Proves or disproves that the side to move can force mate in at most `n`
+/// moves, trying every legal move. Each key comes with every defence and,
+/// for each, every move that still mates in time.
+pub fn solve_mate(board: &Board, n: u32) -> Solution {
+    solve(board, Stipulation::Mate(n))
+}
+
+/// Whether the side to move can force mate in at most `n` moves, without
+/// building the solution.
+pub fn has_forced_mate(board: &Board, n: u32) -> bool {
+    n > 0 && forces(board, false, n)
+}
+#[cfg(test)]
+mod tests {
+    use crate::problem::*;
+    use crate::{Square, Variant};
+
+    #[test]
+    fn test_mate_in_one() {
+        let board = Board::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let solution = solve_mate(&board, 1);
+        assert_eq!(
+            solution.keys,
+            vec![Variation {
+                mv: Move::new(Square::A1, Square::A8, None),
+                replies: Vec::new(),
+            }]
+        );
+        assert!(solution.cooks().is_empty());
+        assert_eq!(solution.text(&board), "1.Ra8#!\n");
+
+        // either rook mates
+        let board = Board::from("6k1/5ppp/8/8/8/8/8/RR4K1 w - - 0 1");
+        assert_eq!(solve_mate(&board, 1).cooks().len(), 1);
+
+        assert!(!solve_mate(&Board::default(), 2).is_solved());
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        // morphy: the rook sacrifice opens b7 for the pawn
+        let board = Board::from("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+        assert!(!has_forced_mate(&board, 1));
+        assert!(has_forced_mate(&board, 2));
+
+        let solution = solve_mate(&board, 2);
+        assert_eq!(solution.keys.len(), 1);
+        let key = &solution.keys[0];
+        assert_eq!(key.mv, Move::new(Square::A1, Square::A6, None));
+
+        // every defence has a mate, and bxa6 only b7
+        assert!(key.replies.iter().all(|reply| !reply.replies.is_empty()));
+        let text = solution.text(&board);
+        assert!(text.starts_with("1.Ra6!\n"));
+        assert!(text.contains("  1...bxa6\n    2.b7#\n"));
+    }
+
+    #[test]
+    fn test_stipulations() {
+        for text in ["#2", "h#3", "s#1"] {
+            assert_eq!(text.parse::<Stipulation>().unwrap().to_string(), text);
+        }
+        assert!("x#2".parse::<Stipulation>().is_err());
+        assert!("h#".parse::<Stipulation>().is_err());
+    }
+
+    #[test]
+    fn test_helpmate() {
+        let board = Board::from("7Q/2k5/8/3K4/8/8/8/8 b - - 0 1");
+        assert!(!solve(&board, Stipulation::Helpmate(1)).is_solved());
+
+        let solution = solve(&board, Stipulation::Helpmate(2));
+        assert_eq!(
+            solution.lines(),
+            vec![vec![
+                Move::new(Square::C7, Square::B7, None),
+                Move::new(Square::D5, Square::C5, None),
+                Move::new(Square::B7, Square::A6, None),
+                Move::new(Square::H8, Square::A8, None),
+            ]]
+        );
+        assert_eq!(
+            solution.text(&board),
+            "1...Kb7!\n  2.Kc5\n    2...Ka6\n      3.Qa8#\n"
+        );
+    }
+
+    #[test]
+    fn test_selfmate() {
+        // the queen checks so that the rook has to take it with mate
+        let board = Board::from("5r2/8/8/8/2Q5/8/PP5p/K6k w - - 0 1");
+        let solution = solve(&board, Stipulation::Selfmate(1));
+        assert_eq!(solution.text(&board), "1.Qf1+!\n  1...Rxf1#\n");
+
+        // a mate is not a selfmate
+        assert!(!solve(&board, Stipulation::Mate(1)).is_solved());
+    }
+
+    #[test]
+    fn test_twins() {
+        let board = Board::from("k7/8/1K6/8/8/8/8/7R b - - 0 1");
+        let stipulation = Stipulation::Helpmate(1);
+        let twins = [
+            Twin::Move(Square::H1, Square::G1),
+            Twin::Duplex,
+            Twin::Remove(Square::H1),
+        ];
+
+        let solutions = solve_twins(&board, stipulation, &twins).unwrap();
+        let mates: Vec<Option<Move>> = solutions
+            .iter()
+            .map(|solution| solution.lines().first().map(|line| line[1]))
+            .collect();
+        assert_eq!(
+            mates,
+            vec![
+                Some(Move::new(Square::H1, Square::H8, None)),
+                Some(Move::new(Square::G1, Square::G8, None)),
+                None,
+                None,
+            ]
+        );
+
+        assert!(Twin::Remove(Square::E4).apply(&board, stipulation).is_err());
+        assert!(Twin::Remove(Square::A8).apply(&board, stipulation).is_err());
+
+        // a horde position without a white king can still be edited
+        let board = Board::start(Variant::Horde);
+        let (twin, _) = Twin::Remove(Square::A1).apply(&board, stipulation).unwrap();
+        assert_eq!(twin.piece_at(Square::A1), None);
+    }
+}
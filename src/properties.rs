@@ -0,0 +1,210 @@
+// property tests over random positions. proptest and quickcheck are not
+// dependencies (the crate has none), so this is a small version of the same
+// idea: games are played out with random legal moves from a seeded
+// generator, a property is checked on every position they reach, and a
+// failure is shrunk to the shortest line from the start position that still
+// fails. PROPERTY_GAMES sets how many games each variant gets.
+
+use crate::{Board, Move, Outcome, Rank, Square, Variant};
+
+const GAMES: u64 = 12;
+const PLIES: usize = 80;
+
+// xorshift64*
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9E3779B97F4A7C15) | 1)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545F4914F6CDD1D) >> 32) as usize % n
+    }
+}
+
+// the moves of a random game from the start position
+fn playout(variant: Variant, seed: u64) -> Vec<Move> {
+    let mut rng = Rng::new(seed);
+    let mut board = Board::start(variant);
+    let mut moves = Vec::new();
+
+    while moves.len() < PLIES && board.outcome().is_none() {
+        let legal = board.legal_moves();
+        let mv = legal[rng.below(legal.len())];
+        board = board.play(mv).unwrap();
+        moves.push(mv);
+    }
+
+    moves
+}
+
+// the position `moves` lead to from the start, if they are all legal
+fn replay(variant: Variant, moves: &[Move]) -> Option<Board> {
+    moves
+        .iter()
+        .try_fold(Board::start(variant), |board, mv| board.play(*mv))
+}
+
+// leaves out pairs of moves, so the same side is still to move, for as long
+// as the line stays legal and still ends in a failure
+fn shrink(
+    variant: Variant,
+    mut line: Vec<Move>,
+    property: &impl Fn(&Board) -> Result<(), String>,
+) -> Vec<Move> {
+    loop {
+        let shorter = (0..line.len().saturating_sub(1))
+            .map(|i| [&line[..i], &line[i + 2..]].concat())
+            .find(|candidate| {
+                replay(variant, candidate).is_some_and(|board| property(&board).is_err())
+            });
+        match shorter {
+            Some(shorter) => line = shorter,
+            None => return line,
+        }
+    }
+}
+
+// checks `property` on every position of random games in each variant, and
+// panics with the shortest failing line it can find
+fn check(variants: &[Variant], property: impl Fn(&Board) -> Result<(), String>) {
+    let games = std::env::var("PROPERTY_GAMES")
+        .ok()
+        .and_then(|games| games.parse().ok())
+        .unwrap_or(GAMES);
+
+    for &variant in variants {
+        for seed in 0..games {
+            let moves = playout(variant, seed);
+            let mut board = Board::start(variant);
+
+            for ply in 0..=moves.len() {
+                if property(&board).is_err() {
+                    let line = shrink(variant, moves[..ply].to_vec(), &property);
+                    let board = replay(variant, &line).unwrap();
+                    let line: Vec<_> = line.iter().map(|mv| mv.uci()).collect();
+                    panic!(
+                        "{variant}, game {seed}: {}\nafter {} ({})",
+                        property(&board).unwrap_err(),
+                        line.join(" "),
+                        board.fen()
+                    );
+                }
+                if let Some(mv) = moves.get(ply) {
+                    board = board.play(*mv).unwrap();
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_make_unmake_restores_board() {
+    check(&Variant::ALL, |board| {
+        for mv in board.legal_moves() {
+            let mut made = *board;
+            let undo = made.make(mv).ok_or(format!("{} can't be made", mv.uci()))?;
+            if Some(made) != board.play(mv) {
+                return Err(format!("making {} isn't playing it", mv.uci()));
+            }
+
+            made.unmake(&undo);
+            if made != *board {
+                return Err(format!("taking {} back leaves {}", mv.uci(), made.fen()));
+            }
+        }
+        Ok(())
+    });
+
+    // an illegal move leaves the board alone
+    let mut board = Board::default();
+    assert!(board
+        .make(Move::new(Square::E2, Square::E5, None))
+        .is_none());
+    assert!(board == Board::default());
+}
+
+#[test]
+fn test_unmoves_find_the_previous_position() {
+    // unmoves only know the castling rights and en passant target the move
+    // itself needs, so the placement and side to move are compared
+    let placement = |board: &Board| board.fen().split(' ').take(2).collect::<Vec<_>>().join(" ");
+    check(&[Variant::Standard], |board| {
+        for mv in board.legal_moves() {
+            let after = board.play(mv).unwrap();
+            let found = after
+                .unmoves()
+                .iter()
+                .any(|unmove| unmove.mv == mv && placement(&unmove.board) == placement(board));
+            if !found {
+                return Err(format!("{} is not taken back", mv.uci()));
+            }
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn test_legal_moves_keep_king_safe() {
+    check(&Variant::ALL, |board| {
+        let mover = board.side_to_move();
+        for mv in board.legal_moves() {
+            // an atomic capture that blows up the other king wins even from
+            // check
+            let after = board.play(mv).unwrap();
+            if after.king_in_check(mover) && after.variant_outcome() != Some(Outcome::Win(mover)) {
+                return Err(format!("{} leaves the king in check", mv.uci()));
+            }
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn test_fen_round_trips() {
+    check(&Variant::ALL, |board| {
+        let fen = board.fen();
+        let parsed = Board::from_variant_fen(board.variant, &fen)?;
+        if parsed != *board {
+            return Err(format!("{fen} reads back as {}", parsed.fen()));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn test_incremental_hash() {
+    check(&Variant::ALL, |board| {
+        if board.hash != board.placement_hash() {
+            return Err("the placement hash is out of step".to_string());
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn test_failures_are_shrunk() {
+    // a white knight off the first rank fails, so whatever else the game
+    // did first, one knight move is all that is left
+    let property = |board: &Board| {
+        if board.knights & board.white & !Rank::R1.bitboard() == 0 {
+            Ok(())
+        } else {
+            Err("a knight is out".to_string())
+        }
+    };
+
+    let moves = playout(Variant::Standard, 0);
+    let ply = (0..=moves.len())
+        .find(|ply| property(&replay(Variant::Standard, &moves[..*ply]).unwrap()).is_err())
+        .unwrap();
+    assert!(ply > 1);
+
+    let line = shrink(Variant::Standard, moves[..ply].to_vec(), &property);
+    assert_eq!(line.len(), 1);
+    assert!(property(&replay(Variant::Standard, &line).unwrap()).is_err());
+}
@@ -0,0 +1,371 @@
+// retrograde analysis: the positions a board could have come from, one move
+// back. every candidate is played forward again with `move_piece` and only
+// kept if it arrives back here, which weeds out retractions that would have
+// left a king in check, castled through an attacked square or cost castling
+// rights the board still has.
+
+use crate::attacks::{KING_ATTACKS, KNIGHT_ATTACKS};
+use crate::castling::{castled_squares, home_rank};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::{Bitboard, Board, Color, File, Move, Piece, Rank, Square, Variant};
+
+// what a move could have taken, one unmove each
+const UNCAPTURES: [Piece; 5] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+];
+
+/// A move taken back.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Unmove {
+    /// The move that leads here from `board`.
+    pub mv: Move,
+    /// The piece the move took, back on `mv.to` or, for en passant, beside
+    /// `mv.from`.
+    pub uncaptured: Option<Piece>,
+    /// The position before the move.
+    pub board: Board,
+}
+
+impl Board {
+    /// Every position one move before this one, with the move that leads
+    /// here. A capture is taken back once for each piece it could have
+    /// taken, promoted pieces turn back into pawns, and castling and en
+    /// passant are undone.
+    ///
+    /// The earlier position gets no castling rights or en passant target
+    /// beyond what the move itself needs, and its clocks are a guess. Only
+    /// standard chess is covered.
+    pub fn unmoves(&self) -> Vec<Unmove> {
+        let mut unmoves = Vec::new();
+        if self.variant != Variant::Standard {
+            return unmoves;
+        }
+
+        let color = !self.side_to_move();
+        let occupied = self.white | self.black;
+        let forward = if color == Color::White { 1 } else { -1 };
+
+        for to in Bitboard(self.side(color)) {
+            let Some(piece) = self.piece_on(to) else {
+                continue;
+            };
+
+            if piece == Piece::Pawn {
+                self.pawn_unmoves(to, color, &mut unmoves);
+                continue;
+            }
+
+            let i = to.index() as usize;
+            let reach = match piece {
+                Piece::Knight => KNIGHT_ATTACKS[i],
+                Piece::Bishop => bishop_attacks(to, occupied),
+                Piece::Rook => rook_attacks(to, occupied),
+                Piece::Queen => queen_attacks(to, occupied),
+                _ => KING_ATTACKS[i],
+            };
+            for from in Bitboard(reach & !occupied) {
+                self.retract_with_uncaptures(Move::new(from, to, None), piece, true, &mut unmoves);
+            }
+
+            // a promoted piece walks back onto the seventh rank as a pawn
+            if piece != Piece::King && to.rank() == home_rank(!color) {
+                for (files, capture) in [(0, false), (-1, true), (1, true)] {
+                    let Some(from) = to.offset(files, -forward) else {
+                        continue;
+                    };
+                    if occupied & from.bit() == 0 {
+                        let mv = Move::new(from, to, Some(piece));
+                        if capture {
+                            self.retract_with_uncaptures(mv, Piece::Pawn, false, &mut unmoves);
+                        } else {
+                            self.retract(mv, Piece::Pawn, None, &mut unmoves);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.uncastlings(color, &mut unmoves);
+        unmoves
+    }
+
+    fn pawn_unmoves(&self, to: Square, color: Color, unmoves: &mut Vec<Unmove>) {
+        let occupied = self.white | self.black;
+        let empty = |square: Option<Square>| square.filter(|square| occupied & square.bit() == 0);
+        let (forward, double_rank, en_passant_rank) = match color {
+            Color::White => (1, Rank::R4, Rank::R6),
+            Color::Black => (-1, Rank::R5, Rank::R3),
+        };
+
+        if let Some(from) = empty(to.offset(0, -forward)) {
+            self.retract(Move::new(from, to, None), Piece::Pawn, None, unmoves);
+
+            if to.rank() == double_rank {
+                if let Some(from) = empty(from.offset(0, -forward)) {
+                    self.retract(Move::new(from, to, None), Piece::Pawn, None, unmoves);
+                }
+            }
+        }
+
+        for files in [-1, 1] {
+            let Some(from) = empty(to.offset(files, -forward)) else {
+                continue;
+            };
+            let mv = Move::new(from, to, None);
+            self.retract_with_uncaptures(mv, Piece::Pawn, false, unmoves);
+
+            // the pawn taken en passant stood beside `from`, having just
+            // come past `to` from its own second rank
+            let taken = Square::new(to.file(), from.rank());
+            if to.rank() == en_passant_rank
+                && empty(Some(taken)).is_some()
+                && empty(to.offset(0, forward)).is_some()
+            {
+                let mut before = self.before(true);
+                before.unset(to);
+                before.set(Piece::Pawn, color, from);
+                before.set(Piece::Pawn, !color, taken);
+                before.en_passant_targets = to.bit();
+                self.keep(before, mv, Some(Piece::Pawn), unmoves);
+            }
+        }
+    }
+
+    fn uncastlings(&self, color: Color, unmoves: &mut Vec<Unmove>) {
+        let rank = home_rank(color);
+
+        for kingside in [true, false] {
+            let (king_to, rook_to) = castled_squares(color, kingside);
+            if self.piece_at(king_to) != Some((Piece::King, color))
+                || self.piece_at(rook_to) != Some((Piece::Rook, color))
+            {
+                continue;
+            }
+
+            // in chess960 the king and rook could have started anywhere on
+            // the back rank, the rook on the side it castled to
+            let starts: Vec<(File, File)> = if self.chess960 {
+                File::all()
+                    .flat_map(|king| {
+                        File::all()
+                            .filter(move |rook| (*rook > king) == kingside && *rook != king)
+                            .map(move |rook| (king, rook))
+                    })
+                    .collect()
+            } else {
+                vec![(File::E, if kingside { File::H } else { File::A })]
+            };
+
+            for (king_file, rook_file) in starts {
+                let (king_from, rook_from) =
+                    (Square::new(king_file, rank), Square::new(rook_file, rank));
+
+                let mut before = self.before(false);
+                before.unset(king_to);
+                before.unset(rook_to);
+                if before.piece_at(king_from).is_some() || before.piece_at(rook_from).is_some() {
+                    continue;
+                }
+
+                before.set(Piece::King, color, king_from);
+                before.set(Piece::Rook, color, rook_from);
+                before.castling.set(color, kingside, Some(rook_file));
+                self.keep(before, Move::new(king_from, rook_from, None), None, unmoves);
+            }
+        }
+    }
+
+    // `mv` taken back as a quiet move if it could have been one, and once
+    // for each piece it could have captured
+    fn retract_with_uncaptures(
+        &self,
+        mv: Move,
+        piece: Piece,
+        quiet: bool,
+        unmoves: &mut Vec<Unmove>,
+    ) {
+        if quiet {
+            self.retract(mv, piece, None, unmoves);
+        }
+        for uncaptured in UNCAPTURES {
+            self.retract(mv, piece, Some(uncaptured), unmoves);
+        }
+    }
+
+    // `piece` back on `mv.from`, with `uncaptured` put back on `mv.to`
+    fn retract(
+        &self,
+        mv: Move,
+        piece: Piece,
+        uncaptured: Option<Piece>,
+        unmoves: &mut Vec<Unmove>,
+    ) {
+        let color = !self.side_to_move();
+        let mut before = self.before(piece == Piece::Pawn || uncaptured.is_some());
+        before.unset(mv.to);
+        before.set(piece, color, mv.from);
+        if let Some(victim) = uncaptured {
+            before.set(victim, !color, mv.to);
+        }
+
+        self.keep(before, mv, uncaptured, unmoves);
+    }
+
+    // this position with the other side to move and the clocks wound back.
+    // a capture or pawn move leaves no trace of the halfmove clock before it
+    fn before(&self, zeroing: bool) -> Board {
+        let mut before = *self;
+        before.whites_turn = !self.whites_turn;
+        before.en_passant_targets = 0;
+        before.halfmove_clock = if zeroing {
+            0
+        } else {
+            self.halfmove_clock.saturating_sub(1)
+        };
+        if self.whites_turn {
+            before.fullmove_number = self.fullmove_number.saturating_sub(1).max(1);
+        }
+
+        before
+    }
+
+    // keeps `before` if it is a legal position and `mv` really leads from
+    // it to this one. the clocks aren't compared, and neither is an en
+    // passant target this board left out
+    fn keep(&self, before: Board, mv: Move, uncaptured: Option<Piece>, unmoves: &mut Vec<Unmove>) {
+        if before.validate().is_err() {
+            return;
+        }
+
+        let mut after = before;
+        if after.move_piece(mv.from, mv.to, mv.promote).is_err() {
+            return;
+        }
+
+        after.halfmove_clock = self.halfmove_clock;
+        after.fullmove_number = self.fullmove_number;
+        if self.en_passant_targets == 0 {
+            after.en_passant_targets = 0;
+        }
+
+        if after == *self {
+            unmoves.push(Unmove {
+                mv,
+                uncaptured,
+                board: before,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retro::*;
+
+    #[test]
+    fn test_unmoves_match_moves() {
+        // every move from a position shows up among the unmoves of where it
+        // leads, from the same placement of pieces
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let board = Board::from(fen);
+            for mv in board.legal_moves() {
+                let after = board.play(mv).unwrap();
+                let uncaptured = board
+                    .is_capture(mv)
+                    .then(|| board.piece_on(mv.to).unwrap_or(Piece::Pawn));
+
+                let found = after.unmoves().iter().any(|unmove| {
+                    unmove.mv == mv
+                        && unmove.uncaptured == uncaptured
+                        && unmove
+                            .board
+                            .fen()
+                            .split(' ')
+                            .take(2)
+                            .eq(fen.split(' ').take(2))
+                });
+                assert!(found, "{fen}: {} is not taken back", mv.uci());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unmoves() {
+        // even the start position has a past: a black knight going home,
+        // maybe taking a white piece there
+        let unmoves = Board::default().unmoves();
+        assert_eq!(unmoves.len(), 4 * 5);
+        let quiet = unmoves.iter().filter(|unmove| unmove.uncaptured.is_none());
+        assert_eq!(quiet.count(), 4);
+        assert!(unmoves
+            .iter()
+            .all(|unmove| unmove.board.piece_on(unmove.mv.from) == Some(Piece::Knight)));
+
+        // the pawn on e4 came from e2 or e3, or took something there
+        let board = Board::from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+        let pawn: Vec<String> = board
+            .unmoves()
+            .iter()
+            .filter(|unmove| unmove.board.piece_on(unmove.mv.from) == Some(Piece::Pawn))
+            .map(|unmove| unmove.board.fen())
+            .collect();
+        assert_eq!(pawn.len(), 2 + 2 * 5);
+        assert!(pawn.contains(&Board::default().fen()));
+
+        // with an en passant target the double step is all that is left
+        let board = Board::from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(board.unmoves().len(), 1);
+
+        // a queen on the eighth rank may have been a pawn, and may have
+        // taken something on the way
+        let board = Board::from("3Q3k/8/6K1/8/8/8/8/8 b - - 0 1");
+        let unpromotions: Vec<Unmove> = board
+            .unmoves()
+            .into_iter()
+            .filter(|unmove| unmove.mv.promote.is_some())
+            .collect();
+        assert_eq!(unpromotions.len(), 1 + 2 * 4);
+        assert!(unpromotions.iter().all(
+            |unmove| unmove.board.piece_at(unmove.mv.from) == Some((Piece::Pawn, Color::White))
+        ));
+
+        // castling, which needs the rights back
+        let board = Board::from("4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+        let castled = board
+            .unmoves()
+            .into_iter()
+            .find(|unmove| unmove.mv == Move::new(Square::E1, Square::H1, None))
+            .unwrap();
+        assert_eq!(castled.board.fen(), "4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        // and en passant from either side, with the black pawn back on d5
+        let board = Board::from("4k3/8/3P4/8/8/8/8/4K3 b - - 0 1");
+        let en_passant: Vec<Unmove> = board
+            .unmoves()
+            .into_iter()
+            .filter(|unmove| unmove.board.en_passant_targets != 0)
+            .collect();
+        let mut fens: Vec<String> = en_passant.iter().map(|unmove| unmove.board.fen()).collect();
+        fens.sort();
+        assert_eq!(
+            fens,
+            vec![
+                "4k3/8/8/2Pp4/8/8/8/4K3 w - d6 0 1",
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            ]
+        );
+        assert!(en_passant
+            .iter()
+            .all(|unmove| unmove.uncaptured == Some(Piece::Pawn)));
+    }
+}
@@ -0,0 +1,181 @@
+// standard algebraic notation. writing a move needs the legal moves, to know
+// how much of its origin to spell out; reading one looks for the legal move
+// that is written the same way
+
+use crate::fen::piece_to_char;
+use crate::{Board, Color, Move, Piece};
+
+// the move without check or mate marks, annotations or '=' before a promotion
+fn normalize(san: &str) -> String {
+    let san = san.trim_end_matches(['+', '#', '!', '?']).replace('=', "");
+    let san = san.replace('0', "O");
+
+    // a pawn drop may leave out the pawn
+    match san.strip_prefix('@') {
+        Some(square) => format!("P@{square}"),
+        None => san,
+    }
+}
+
+impl Board {
+    /// `mv` in standard algebraic notation, such as `Nbd2`, `exd8=Q+`, `O-O`
+    /// or the drop `N@f3`. `mv` has to be legal.
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = self.san_without_suffix(mv);
+
+        if let Some(board) = self.play(mv) {
+            if board.in_check() {
+                san.push(if board.legal_moves().is_empty() {
+                    '#'
+                } else {
+                    '+'
+                });
+            }
+        }
+
+        san
+    }
+
+    fn san_without_suffix(&self, mv: Move) -> String {
+        if let Some(piece) = mv.drop {
+            return format!("{}@{}", piece_to_char(piece, Color::White), mv.to);
+        }
+
+        let Some((piece, color)) = self.piece_at(mv.from) else {
+            return mv.uci();
+        };
+
+        if piece == Piece::King && self.side(color) & mv.to.bit() != 0 {
+            return if mv.to.file() > mv.from.file() {
+                "O-O".to_string()
+            } else {
+                "O-O-O".to_string()
+            };
+        }
+
+        let capture = self.is_capture(mv);
+        let mut san = String::new();
+
+        if piece == Piece::Pawn {
+            if capture {
+                san.push(mv.from.file().char());
+            }
+        } else {
+            san.push(piece_to_char(piece, Color::White));
+
+            // other pieces of the same kind that could also go there
+            let others: Vec<_> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.to == mv.to
+                        && other.from != mv.from
+                        && other.drop.is_none()
+                        && self.piece_on(other.from) == Some(piece)
+                })
+                .collect();
+
+            if !others.is_empty() {
+                let same_file = others.iter().any(|o| o.from.file() == mv.from.file());
+                let same_rank = others.iter().any(|o| o.from.rank() == mv.from.rank());
+
+                if !same_file {
+                    san.push(mv.from.file().char());
+                } else if !same_rank {
+                    san.push_str(&mv.from.rank().to_string());
+                } else {
+                    san.push_str(&mv.from.to_string());
+                }
+            }
+        }
+
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+
+        if let Some(promote) = mv.promote {
+            san.push('=');
+            san.push(piece_to_char(promote, Color::White));
+        }
+
+        san
+    }
+
+    /// Reads a move in standard algebraic notation. Check marks and
+    /// annotations are ignored, and castling may be written with zeros.
+    pub fn parse_san(&self, san: &str) -> Result<Move, String> {
+        let wanted = normalize(san);
+
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| normalize(&self.san_without_suffix(*mv)) == wanted)
+            .ok_or(format!("not a legal move: {san}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_san() {
+        let board = Board::default();
+        let mv = board.parse_san("Nf3").unwrap();
+        assert_eq!(mv, Move::new(Square::G1, Square::F3, None));
+        assert_eq!(board.san(mv), "Nf3");
+        assert!(board.parse_san("Nd2").is_err());
+
+        // knights on b1 and f3 can both reach d2, rooks on a1 and a5 a3
+        let board = Board::from("4k3/8/8/R7/8/5N2/4P3/RN2K3 w - - 0 1");
+        assert_eq!(board.san(Move::new(Square::B1, Square::D2, None)), "Nbd2");
+        assert_eq!(board.san(Move::new(Square::A1, Square::A3, None)), "R1a3");
+        assert_eq!(
+            board.parse_san("R5a3"),
+            Ok(Move::new(Square::A5, Square::A3, None))
+        );
+    }
+
+    #[test]
+    fn test_captures_castling_and_promotions() {
+        let board = Board::from("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+        assert_eq!(board.san(Move::new(Square::E5, Square::D6, None)), "exd6");
+        assert_eq!(board.san(Move::new(Square::E1, Square::H1, None)), "O-O");
+        assert_eq!(
+            board.san(Move::new(Square::B7, Square::A8, Some(Piece::Queen))),
+            "bxa8=Q+"
+        );
+        assert_eq!(
+            board.parse_san("0-0-0"),
+            Ok(Move::new(Square::E1, Square::A1, None))
+        );
+        assert_eq!(
+            board.parse_san("b8N"),
+            Ok(Move::new(Square::B7, Square::B8, Some(Piece::Knight)))
+        );
+
+        // scholar's mate
+        let board =
+            Board::from("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4");
+        assert_eq!(board.san(Move::new(Square::F3, Square::F7, None)), "Qxf7#");
+    }
+
+    #[test]
+    fn test_drops() {
+        let board = Board::from("4k3/8/8/8/8/8/8/4K3[NP] w - - 0 1");
+        assert_eq!(board.san(Move::drop(Piece::Knight, Square::F6)), "N@f6+");
+        assert_eq!(
+            board.parse_san("@e4"),
+            Ok(Move::drop(Piece::Pawn, Square::E4))
+        );
+        assert_eq!(
+            board.parse_san("P@e4"),
+            Ok(Move::drop(Piece::Pawn, Square::E4))
+        );
+        assert_eq!(
+            board.parse_uci("N@f3"),
+            Ok(Move::drop(Piece::Knight, Square::F3))
+        );
+        assert_eq!(Move::drop(Piece::Knight, Square::F3).uci(), "N@f3");
+    }
+}
@@ -0,0 +1,729 @@
+// alpha-beta search with a shared transposition table.
+//
+// with more than one thread this runs as lazy SMP: every thread searches the
+// same root position with iterative deepening, helpers at slightly different
+// depths, and they only cooperate through the transposition table. the main
+// thread decides when to stop; the deepest finished iteration wins.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::eval::evaluate;
+use crate::movepick::{is_tactical, Heuristics, MovePicker};
+use crate::syzygy::Tablebases;
+use crate::{Board, Move, Outcome, Piece, Square};
+
+pub const MATE: i32 = 30_000;
+pub const INFINITY: i32 = 31_000;
+pub const MAX_PLY: i32 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub mv: Option<Move>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+// `key` holds the zobrist key xored with `data`, so a torn write from another
+// thread just fails verification instead of returning garbage
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes.max(1) << 20) / std::mem::size_of::<Slot>();
+        let len = 1usize << (usize::BITS - 1 - wanted.leading_zeros());
+
+        Self {
+            slots: (0..len)
+                .map(|_| Slot {
+                    key: AtomicU64::new(0),
+                    data: AtomicU64::new(0),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+
+        Some(TtEntry {
+            mv: decode_move(((data >> 16) & 0xFFFF) as u16),
+            score: data as u16 as i16 as i32,
+            depth: (data >> 32) as u8,
+            bound: match (data >> 40) & 3 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+        })
+    }
+
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        let slot = self.slot(key);
+
+        let old = slot.data.load(Ordering::Relaxed);
+        if old != 0
+            && slot.key.load(Ordering::Relaxed) ^ old == key
+            && (old >> 32) as u8 > entry.depth
+            && entry.bound != Bound::Exact
+        {
+            return;
+        }
+
+        let data = (entry.score as i16 as u16 as u64)
+            | (entry.mv.map(encode_move).unwrap_or(0) as u64) << 16
+            | (entry.depth as u64) << 32
+            | (entry.bound as u64) << 40
+            // so that an entry is never all zeroes
+            | 1 << 42;
+
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+// a drop is stored as a move from its square to itself, with the dropped
+// piece where a promotion would go
+fn encode_move(mv: Move) -> u16 {
+    let from = mv.from.index() as u16;
+    let to = mv.to.index() as u16;
+    let promote = match mv.promote.or(mv.drop) {
+        None => 0,
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(Piece::Pawn) => 5,
+        Some(Piece::King) => 6,
+        Some(_) => 4,
+    };
+
+    from | to << 6 | promote << 12
+}
+
+fn decode_move(bits: u16) -> Option<Move> {
+    let from = Square::from_index((bits & 63) as u8)?;
+    let to = Square::from_index(((bits >> 6) & 63) as u8)?;
+
+    let piece = match bits >> 12 {
+        1 => Some(Piece::Knight),
+        2 => Some(Piece::Bishop),
+        3 => Some(Piece::Rook),
+        4 => Some(Piece::Queen),
+        5 => Some(Piece::Pawn),
+        6 => Some(Piece::King),
+        _ => None,
+    };
+
+    if from == to {
+        piece.map(|piece| Move::drop(piece, to))
+    } else {
+        Some(Move::new(from, to, piece))
+    }
+}
+
+// a finished game scores like a mate, from the side to move's point of view
+fn outcome_score(board: &Board, outcome: Outcome, ply: i32) -> i32 {
+    match outcome {
+        Outcome::Win(color) if color == board.side_to_move() => MATE - ply,
+        Outcome::Win(_) => -MATE + ply,
+        Outcome::Draw => 0,
+    }
+}
+
+// mate scores are stored relative to the node, not the root
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE - MAX_PLY {
+        score + ply
+    } else if score <= -MATE + MAX_PLY {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE - MAX_PLY {
+        score - ply
+    } else if score <= -MATE + MAX_PLY {
+        score + ply
+    } else {
+        score
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub depth: u8,
+    /// Number of threads, including the main one. Anything above one runs a
+    /// lazy SMP search, which is not reproducible between runs.
+    pub threads: usize,
+    pub time: Option<Duration>,
+    /// Endgame tables to pick the root moves from, when the position is in
+    /// them.
+    pub tablebases: Option<Arc<Tablebases>>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            depth: 6,
+            threads: 1,
+            time: None,
+            tablebases: None,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// A single threaded, depth limited search: the same position and options
+    /// always produce the same result.
+    pub fn deterministic(depth: u8) -> Self {
+        Self {
+            depth,
+            threads: 1,
+            time: None,
+            tablebases: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+}
+
+pub struct Searcher {
+    tt: TranspositionTable,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl Searcher {
+    pub fn new(hash_megabytes: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(hash_megabytes),
+        }
+    }
+
+    pub fn table(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    /// Searches `board` for the side to move. Returns `None` if there are no
+    /// legal moves.
+    pub fn search(&self, board: &Board, options: &SearchOptions) -> Option<SearchResult> {
+        let mut root_moves = board.legal_moves();
+        if root_moves.is_empty() {
+            return None;
+        }
+
+        // only the moves that keep the tablebase result are searched
+        if let Some(tablebases) = &options.tablebases {
+            if let Ok(moves) = tablebases.root_moves(board) {
+                root_moves = moves;
+            }
+        }
+
+        let stop = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        let start = Instant::now();
+        let threads = options.threads.max(1);
+        let max_depth = options.depth.max(1);
+
+        let finished = thread::scope(|scope| {
+            let helpers = (1..threads)
+                .map(|id| {
+                    let mut worker = Worker::new(id, &self.tt, &stop, start, options.time);
+                    let root_moves = &root_moves;
+                    let nodes = &nodes;
+                    scope.spawn(move || {
+                        let result = worker.iterate(board, root_moves, max_depth);
+                        nodes.fetch_add(worker.nodes, Ordering::Relaxed);
+                        result
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut main = Worker::new(0, &self.tt, &stop, start, options.time);
+            let mut finished = vec![main.iterate(board, &root_moves, max_depth)];
+            nodes.fetch_add(main.nodes, Ordering::Relaxed);
+
+            stop.store(true, Ordering::Relaxed);
+            for helper in helpers {
+                finished.push(helper.join().expect("search thread panicked"));
+            }
+
+            finished
+        });
+
+        // on equal depths, prefer whichever thread came first (the main one)
+        let mut best: Option<Iteration> = None;
+        for iteration in finished.into_iter().flatten() {
+            if best.is_none_or(|best| iteration.depth > best.depth) {
+                best = Some(iteration);
+            }
+        }
+
+        // even if every thread was stopped during its first iteration, there is
+        // still a legal move to return
+        let best = best.unwrap_or(Iteration {
+            mv: root_moves[0],
+            score: 0,
+            depth: 0,
+        });
+
+        Some(SearchResult {
+            best_move: best.mv,
+            score: best.score,
+            depth: best.depth,
+            nodes: nodes.load(Ordering::Relaxed),
+            pv: self.principal_variation(board, best.mv, best.depth),
+        })
+    }
+
+    fn principal_variation(&self, board: &Board, first: Move, depth: u8) -> Vec<Move> {
+        let mut pv = vec![first];
+        let mut board = match board.play(first) {
+            Some(board) => board,
+            None => return pv,
+        };
+
+        while pv.len() < depth as usize {
+            let Some(mv) = self.tt.probe(board.hash()).and_then(|entry| entry.mv) else {
+                break;
+            };
+            let Some(next) = board.play(mv) else {
+                break;
+            };
+
+            pv.push(mv);
+            board = next;
+        }
+
+        pv
+    }
+}
+
+/// Searches `board` with a fresh transposition table.
+pub fn search(board: &Board, options: &SearchOptions) -> Option<SearchResult> {
+    Searcher::default().search(board, options)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Iteration {
+    mv: Move,
+    score: i32,
+    depth: u8,
+}
+
+struct Worker<'a> {
+    id: usize,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    start: Instant,
+    time: Option<Duration>,
+    nodes: u64,
+    heuristics: Heuristics,
+}
+
+impl<'a> Worker<'a> {
+    fn new(
+        id: usize,
+        tt: &'a TranspositionTable,
+        stop: &'a AtomicBool,
+        start: Instant,
+        time: Option<Duration>,
+    ) -> Self {
+        Self {
+            id,
+            tt,
+            stop,
+            start,
+            time,
+            nodes: 0,
+            heuristics: Heuristics::new(),
+        }
+    }
+
+    fn stopped(&mut self) -> bool {
+        // only the main thread keeps time, helpers just follow it
+        if self.id == 0 && self.nodes & 1023 == 0 {
+            if let Some(time) = self.time {
+                if self.start.elapsed() >= time {
+                    self.stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    fn iterate(&mut self, board: &Board, root_moves: &[Move], max_depth: u8) -> Option<Iteration> {
+        let mut best = None;
+
+        // odd helpers run one ply ahead, so the threads fill the table with
+        // different parts of the tree, and finish one iteration early rather
+        // than repeating the main thread's last one
+        for depth in 1 + (self.id % 2) as u8..=max_depth {
+            let Some(iteration) = self.root(board, root_moves, depth) else {
+                break;
+            };
+
+            best = Some(iteration);
+            if iteration.score.abs() >= MATE - MAX_PLY {
+                break;
+            }
+        }
+
+        best
+    }
+
+    fn root(&mut self, board: &Board, root_moves: &[Move], depth: u8) -> Option<Iteration> {
+        let key = board.hash();
+        let tt_move = self.tt.probe(key).and_then(|entry| entry.mv);
+        let mut picker = MovePicker::new(board, &self.heuristics, tt_move, 0, None);
+
+        let mut alpha = -INFINITY;
+        let mut best = root_moves[0];
+
+        while let Some(mv) = picker.next_move(&self.heuristics) {
+            if !root_moves.contains(&mv) {
+                continue;
+            }
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+            let score = -self.negamax(&child, depth as i32 - 1, -INFINITY, -alpha, 1, Some(mv));
+
+            if self.stopped() {
+                return None;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best = mv;
+            }
+        }
+
+        self.tt.store(
+            key,
+            TtEntry {
+                mv: Some(best),
+                score: score_to_tt(alpha, 0),
+                depth,
+                bound: Bound::Exact,
+            },
+        );
+
+        Some(Iteration {
+            mv: best,
+            score: alpha,
+            depth,
+        })
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        mut depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        previous: Option<Move>,
+    ) -> i32 {
+        if self.stopped() {
+            return 0;
+        }
+
+        if let Some(outcome) = board.variant_outcome() {
+            return outcome_score(board, outcome, ply);
+        }
+
+        let in_check = board.in_check();
+        if in_check {
+            depth += 1;
+        }
+
+        if depth <= 0 {
+            return self.quiescence(board, alpha, beta, ply);
+        }
+
+        self.nodes += 1;
+        if ply >= MAX_PLY {
+            return evaluate(board);
+        }
+
+        let key = board.hash();
+        let mut tt_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            tt_move = entry.mv;
+
+            if entry.depth as i32 >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut legal = 0;
+        let mut quiets = Vec::new();
+
+        let mut picker = MovePicker::new(board, &self.heuristics, tt_move, ply as usize, previous);
+        while let Some(mv) = picker.next_move(&self.heuristics) {
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+            legal += 1;
+
+            let score = -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1, Some(mv));
+            if self.stop.load(Ordering::Relaxed) {
+                return 0;
+            }
+
+            let quiet = !is_tactical(board, mv);
+            if score > best {
+                best = score;
+                best_move = Some(mv);
+
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        if quiet {
+                            self.heuristics.update(
+                                board.side_to_move(),
+                                mv,
+                                ply as usize,
+                                depth,
+                                previous,
+                                &quiets,
+                            );
+                        }
+                        break;
+                    }
+                }
+            }
+
+            if quiet {
+                quiets.push(mv);
+            }
+        }
+
+        if legal == 0 {
+            return outcome_score(board, board.outcome_without_moves(), ply);
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.tt.store(
+            key,
+            TtEntry {
+                mv: best_move,
+                score: score_to_tt(best, ply),
+                depth: depth.min(u8::MAX as i32) as u8,
+                bound,
+            },
+        );
+
+        best
+    }
+
+    fn quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        self.nodes += 1;
+        if let Some(outcome) = board.variant_outcome() {
+            return outcome_score(board, outcome, ply);
+        }
+        if ply >= MAX_PLY {
+            return evaluate(board);
+        }
+
+        // in check there is no standing pat: every evasion gets searched
+        let in_check = board.in_check();
+        let mut best = if in_check { -INFINITY } else { evaluate(board) };
+
+        if best >= beta {
+            return best;
+        }
+        alpha = alpha.max(best);
+
+        let mut picker = if in_check {
+            MovePicker::new(board, &self.heuristics, None, ply as usize, None)
+        } else {
+            MovePicker::captures(board)
+        };
+
+        let mut legal = 0;
+        while let Some(mv) = picker.next_move(&self.heuristics) {
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+            legal += 1;
+
+            let score = -self.quiescence(&child, -beta, -alpha, ply + 1);
+            if self.stopped() {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if in_check && legal == 0 {
+            return -MATE + ply;
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::*;
+
+    #[test]
+    fn test_mate_in_one() {
+        let board = Board::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = search(&board, &SearchOptions::deterministic(3)).unwrap();
+
+        assert_eq!(result.best_move, Move::new(Square::A1, Square::A8, None));
+        assert_eq!(result.score, MATE - 1);
+    }
+
+    #[test]
+    fn test_takes_hanging_queen() {
+        let board = Board::from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let result = search(&board, &SearchOptions::deterministic(3)).unwrap();
+
+        assert_eq!(result.best_move, Move::new(Square::D2, Square::D5, None));
+        assert_eq!(result.pv[0], result.best_move);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let board = Board::from("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let options = SearchOptions::deterministic(3);
+
+        let first = search(&board, &options).unwrap();
+        let second = search(&board, &options).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_lazy_smp() {
+        let board = Board::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let options = SearchOptions {
+            depth: 4,
+            threads: 4,
+            time: None,
+            tablebases: None,
+        };
+
+        let result = search(&board, &options).unwrap();
+        assert_eq!(result.best_move, Move::new(Square::A1, Square::A8, None));
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn test_helper_depths() {
+        let board = Board::from("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let root_moves = board.legal_moves();
+        let stop = AtomicBool::new(false);
+        let start = Instant::now();
+
+        // an odd helper searches depths 2 and 3 once each, and nothing else
+        let tt = TranspositionTable::new(1);
+        let mut helper = Worker::new(1, &tt, &stop, start, None);
+        let result = helper.iterate(&board, &root_moves, 3).unwrap();
+        assert_eq!(result.depth, 3);
+
+        let tt = TranspositionTable::new(1);
+        let mut main = Worker::new(0, &tt, &stop, start, None);
+        main.root(&board, &root_moves, 2).unwrap();
+        main.root(&board, &root_moves, 3).unwrap();
+        assert_eq!(helper.nodes, main.nodes);
+    }
+
+    #[test]
+    fn test_no_moves() {
+        // black is checkmated
+        let board = Board::from("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        assert!(search(&board, &SearchOptions::deterministic(2)).is_none());
+    }
+
+    #[test]
+    fn test_table() {
+        let tt = TranspositionTable::new(1);
+        let entry = TtEntry {
+            mv: Some(Move::new(Square::G7, Square::H8, Some(Piece::Knight))),
+            score: -MATE + 3,
+            depth: 7,
+            bound: Bound::Lower,
+        };
+
+        tt.store(0xDEADBEEF, entry);
+        assert_eq!(tt.probe(0xDEADBEEF), Some(entry));
+        assert_eq!(tt.probe(0xBEEF), None);
+
+        tt.clear();
+        assert_eq!(tt.probe(0xDEADBEEF), None);
+    }
+}
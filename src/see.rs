@@ -0,0 +1,158 @@
+// static exchange evaluation: plays out every capture on the target square,
+// least valuable attacker first, and lets each side stop capturing whenever
+// that is better for them
+
+use crate::eval::piece_value;
+use crate::{Board, Move, Piece, Square};
+
+// a king can only be "captured" if nothing else is left to recapture
+const KING_VALUE: i32 = 20_000;
+
+fn see_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::King => KING_VALUE,
+        piece => piece_value(piece),
+    }
+}
+
+impl Board {
+    /// The material balance, in centipawns, for the side to move after `mv`
+    /// and every recapture on its destination square have been played out.
+    pub fn see(&self, mv: Move) -> i32 {
+        let Some(mut attacker) = self.piece_on(mv.from) else {
+            return 0;
+        };
+
+        // castling (the king taking its own rook) never captures anything, and
+        // the move generator already refuses to castle through attacked squares
+        if attacker == Piece::King && self.side(self.side_to_move()) & mv.to.bit() != 0 {
+            return 0;
+        }
+
+        let mut occupied = self.white | self.black;
+        let mut gain = [0; 32];
+
+        gain[0] = match self.piece_on(mv.to) {
+            Some(victim) => see_value(victim),
+            None if attacker == Piece::Pawn && self.en_passant_targets & mv.to.bit() != 0 => {
+                // the captured pawn is beside the capturing one, not on the target
+                occupied &= !Square::new(mv.to.file(), mv.from.rank()).bit();
+                piece_value(Piece::Pawn)
+            }
+            None => 0,
+        };
+
+        if let Some(promote) = mv.promote {
+            gain[0] += piece_value(promote) - piece_value(Piece::Pawn);
+            attacker = promote;
+        }
+
+        let mut from = mv.from.bit();
+        let mut side = self.side_to_move();
+        let mut depth = 0;
+
+        loop {
+            depth += 1;
+            // what the last capture gains if it is recaptured
+            gain[depth] = see_value(attacker) - gain[depth - 1];
+            if (-gain[depth - 1]).max(gain[depth]) < 0 {
+                break;
+            }
+
+            occupied &= !from;
+            side = !side;
+
+            let attackers = self.attackers(mv.to, occupied) & occupied;
+            let Some((piece, bit)) = self.least_valuable(attackers & self.side(side)) else {
+                break;
+            };
+
+            // the king may not recapture into a defended square
+            if piece == Piece::King && attackers & self.side(!side) != 0 {
+                break;
+            }
+
+            attacker = piece;
+            from = bit;
+
+            if depth + 1 >= gain.len() {
+                break;
+            }
+        }
+
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+
+        gain[0]
+    }
+
+    /// Whether the static exchange after `mv` gains at least `threshold`.
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        self.see(mv) >= threshold
+    }
+
+    fn least_valuable(&self, attackers: u64) -> Option<(Piece, u64)> {
+        Piece::ALL.into_iter().find_map(|piece| {
+            let bb = attackers & self.pieces(piece);
+            (bb != 0).then(|| (piece, bb & bb.wrapping_neg()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        let board = Board::from(fen);
+        board.see(Move::new(
+            uci[..2].parse().unwrap(),
+            uci[2..4].parse().unwrap(),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_see() {
+        // undefended pawn
+        assert_eq!(
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+            100
+        );
+
+        // a pawn defended by a knight, attacked by a rook and a queen stacked
+        // behind it
+        assert_eq!(
+            see(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5"
+            ),
+            100 - 320
+        );
+
+        // quiet move onto a square defended by a pawn
+        assert_eq!(see("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1", "e4c5"), -320);
+    }
+
+    #[test]
+    fn test_x_rays() {
+        // the rook on e1 backs up the one on e2 through it, so white ends up a
+        // knight ahead after the rooks are traded
+        let fen = "4k3/4r3/8/4n3/8/8/4R3/4R1K1 w - - 0 1";
+        assert_eq!(see(fen, "e2e5"), 320);
+
+        // without the second rook, white gives up a rook for the knight
+        let fen = "4k3/4r3/8/4n3/8/8/4R3/6K1 w - - 0 1";
+        assert_eq!(see(fen, "e2e5"), 320 - 500);
+    }
+
+    #[test]
+    fn test_see_ge() {
+        let board = Board::from("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1");
+        let mv = Move::new(Square::E4, Square::C5, None);
+        assert!(board.see_ge(mv, -320));
+        assert!(!board.see_ge(mv, 0));
+    }
+}
@@ -0,0 +1,328 @@
+// board coordinates. files and ranks count from zero, so a1 is file 0, rank 0,
+// and a square's index (and bit in a bitboard) is `file + rank * 8`
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct File(u8);
+
+impl File {
+    pub const A: File = File(0);
+    pub const B: File = File(1);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+
+    pub const fn new(index: u8) -> Option<File> {
+        if index < 8 {
+            Some(File(index))
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn offset(self, by: i8) -> Option<File> {
+        File::new(self.0.checked_add_signed(by)?)
+    }
+
+    pub fn char(self) -> char {
+        (b'a' + self.0) as char
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        match c.to_ascii_lowercase() {
+            c @ 'a'..='h' => Some(File(c as u8 - b'a')),
+            _ => None,
+        }
+    }
+
+    /// Every square on this file.
+    pub const fn bitboard(self) -> u64 {
+        0x0101010101010101 << self.0
+    }
+
+    pub fn all() -> impl DoubleEndedIterator<Item = File> {
+        (0..8).map(File)
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.char())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Rank(u8);
+
+impl Rank {
+    pub const R1: Rank = Rank(0);
+    pub const R2: Rank = Rank(1);
+    pub const R3: Rank = Rank(2);
+    pub const R4: Rank = Rank(3);
+    pub const R5: Rank = Rank(4);
+    pub const R6: Rank = Rank(5);
+    pub const R7: Rank = Rank(6);
+    pub const R8: Rank = Rank(7);
+
+    pub const fn new(index: u8) -> Option<Rank> {
+        if index < 8 {
+            Some(Rank(index))
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn offset(self, by: i8) -> Option<Rank> {
+        Rank::new(self.0.checked_add_signed(by)?)
+    }
+
+    pub fn char(self) -> char {
+        (b'1' + self.0) as char
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c {
+            '1'..='8' => Some(Rank(c as u8 - b'1')),
+            _ => None,
+        }
+    }
+
+    /// Every square on this rank.
+    pub const fn bitboard(self) -> u64 {
+        0xFF << (self.0 * 8)
+    }
+
+    pub fn all() -> impl DoubleEndedIterator<Item = Rank> {
+        (0..8).map(Rank)
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.char())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const B1: Square = Square(1);
+    pub const C1: Square = Square(2);
+    pub const D1: Square = Square(3);
+    pub const E1: Square = Square(4);
+    pub const F1: Square = Square(5);
+    pub const G1: Square = Square(6);
+    pub const H1: Square = Square(7);
+    pub const A2: Square = Square(8);
+    pub const B2: Square = Square(9);
+    pub const C2: Square = Square(10);
+    pub const D2: Square = Square(11);
+    pub const E2: Square = Square(12);
+    pub const F2: Square = Square(13);
+    pub const G2: Square = Square(14);
+    pub const H2: Square = Square(15);
+    pub const A3: Square = Square(16);
+    pub const B3: Square = Square(17);
+    pub const C3: Square = Square(18);
+    pub const D3: Square = Square(19);
+    pub const E3: Square = Square(20);
+    pub const F3: Square = Square(21);
+    pub const G3: Square = Square(22);
+    pub const H3: Square = Square(23);
+    pub const A4: Square = Square(24);
+    pub const B4: Square = Square(25);
+    pub const C4: Square = Square(26);
+    pub const D4: Square = Square(27);
+    pub const E4: Square = Square(28);
+    pub const F4: Square = Square(29);
+    pub const G4: Square = Square(30);
+    pub const H4: Square = Square(31);
+    pub const A5: Square = Square(32);
+    pub const B5: Square = Square(33);
+    pub const C5: Square = Square(34);
+    pub const D5: Square = Square(35);
+    pub const E5: Square = Square(36);
+    pub const F5: Square = Square(37);
+    pub const G5: Square = Square(38);
+    pub const H5: Square = Square(39);
+    pub const A6: Square = Square(40);
+    pub const B6: Square = Square(41);
+    pub const C6: Square = Square(42);
+    pub const D6: Square = Square(43);
+    pub const E6: Square = Square(44);
+    pub const F6: Square = Square(45);
+    pub const G6: Square = Square(46);
+    pub const H6: Square = Square(47);
+    pub const A7: Square = Square(48);
+    pub const B7: Square = Square(49);
+    pub const C7: Square = Square(50);
+    pub const D7: Square = Square(51);
+    pub const E7: Square = Square(52);
+    pub const F7: Square = Square(53);
+    pub const G7: Square = Square(54);
+    pub const H7: Square = Square(55);
+    pub const A8: Square = Square(56);
+    pub const B8: Square = Square(57);
+    pub const C8: Square = Square(58);
+    pub const D8: Square = Square(59);
+    pub const E8: Square = Square(60);
+    pub const F8: Square = Square(61);
+    pub const G8: Square = Square(62);
+    pub const H8: Square = Square(63);
+
+    pub const fn new(file: File, rank: Rank) -> Square {
+        Square(file.0 + rank.0 * 8)
+    }
+
+    pub const fn from_index(index: u8) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index))
+        } else {
+            None
+        }
+    }
+
+    /// The square at file `x`, rank `y`, as used by the coordinate based
+    /// functions.
+    pub const fn from_coords(x: u8, y: u8) -> Option<Square> {
+        if x < 8 && y < 8 {
+            Some(Square(x + y * 8))
+        } else {
+            None
+        }
+    }
+
+    // the lowest set bit of a non-empty bitboard
+    pub(crate) const fn from_bit(bb: u64) -> Square {
+        Square(bb.trailing_zeros() as u8)
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    pub const fn file(self) -> File {
+        File(self.0 & 7)
+    }
+
+    pub const fn rank(self) -> Rank {
+        Rank(self.0 >> 3)
+    }
+
+    /// `(file, rank)`, as used by the coordinate based functions.
+    pub const fn coords(self) -> (u8, u8) {
+        (self.0 & 7, self.0 >> 3)
+    }
+
+    pub const fn bit(self) -> u64 {
+        1 << self.0
+    }
+
+    /// The square `files` to the right and `ranks` up, if it is on the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Square> {
+        Some(Square::new(
+            self.file().offset(files)?,
+            self.rank().offset(ranks)?,
+        ))
+    }
+
+    /// a1, b1, ..., h1, a2, ..., h8.
+    pub fn all() -> impl DoubleEndedIterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => Ok(Square::new(
+                File::from_char(file).ok_or(format!("invalid file: {file}"))?,
+                Rank::from_char(rank).ok_or(format!("invalid rank: {rank}"))?,
+            )),
+            _ => Err(format!("a square is a file and a rank, like e4, got: {s}")),
+        }
+    }
+}
+
+impl From<Square> for (u8, u8) {
+    fn from(square: Square) -> Self {
+        square.coords()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("e4".parse::<Square>(), Ok(Square::E4));
+        assert_eq!("H8".parse::<Square>(), Ok(Square::H8));
+        assert!("i1".parse::<Square>().is_err());
+        assert!("a9".parse::<Square>().is_err());
+        assert!("a10".parse::<Square>().is_err());
+        assert!("".parse::<Square>().is_err());
+
+        for square in Square::all() {
+            assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+        }
+    }
+
+    #[test]
+    fn test_coords() {
+        assert_eq!(Square::E4.coords(), (4, 3));
+        assert_eq!(Square::E4.index(), 28);
+        assert_eq!(Square::E4.bit(), 1 << 28);
+        assert_eq!(Square::from_coords(4, 3), Some(Square::E4));
+        assert_eq!(Square::from_coords(8, 0), None);
+        assert_eq!(Square::new(File::E, Rank::R4), Square::E4);
+        assert_eq!(Square::E4.file(), File::E);
+        assert_eq!(Square::E4.rank(), Rank::R4);
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(Square::E4.offset(1, 2), Some(Square::F6));
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::H8.offset(0, 1), None);
+        assert_eq!(Square::B2.offset(-1, -1), Some(Square::A1));
+        assert_eq!(File::H.offset(1), None);
+        assert_eq!(Rank::R1.offset(7), Some(Rank::R8));
+    }
+
+    #[test]
+    fn test_all() {
+        assert_eq!(Square::all().count(), 64);
+        assert_eq!(Square::all().next(), Some(Square::A1));
+        assert_eq!(Square::all().last(), Some(Square::H8));
+        assert_eq!(
+            File::all().map(|f| f.char()).collect::<String>(),
+            "abcdefgh"
+        );
+        assert_eq!(Rank::all().map(|r| r.bitboard()).fold(0, |a, b| a | b), !0);
+    }
+}
@@ -0,0 +1,156 @@
+// symmetry checks for move generators and evaluators. a position and its
+// colour swap (`Board::swap_colors`) are the same game seen from the other
+// side, so their legal moves have to mirror each other and an evaluation from
+// the side to move has to come out the same. a pawn pushed the wrong way or a
+// piece-square table indexed from the wrong side shows up as a difference.
+//
+// the checks return a description of the first difference so they can be run
+// from downstream tests against other evaluators, with `CORPUS` or their own
+// positions.
+
+use crate::{
+    bitboard_flip_vertical, black_captures, black_moves, white_captures, white_moves, Board, Move,
+    Square,
+};
+
+/// Positions with castling rights, en passant targets, promotions, checks
+/// and pins, some with black to move.
+pub const CORPUS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "rnbqkb1r/pp1p1ppp/5n2/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq c6 0 4",
+    "4k3/8/8/8/1pP5/8/8/4K3 b - c3 0 1",
+    "8/P1k5/8/8/8/8/5Kp1/8 w - - 0 1",
+    "4k3/8/8/8/8/8/8/4K2R b K - 0 1",
+    "3k4/8/8/8/8/8/3q4/R3K3 w Q - 0 1",
+    "8/8/8/8/8/4k3/4p3/4K3 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 b - - 0 1",
+    "r1bq1rk1/ppp2ppp/2n2n2/3pp3/1bPP4/2N1PN2/PP1B1PPP/R2QKB1R w KQ - 4 7",
+];
+
+// the legacy whole-side move and capture bitboards
+type Mask = fn(Board) -> u64;
+
+fn mirrored(mv: Move) -> Move {
+    let mirror = |square: Square| Square::from_index(square.index() ^ 56).unwrap();
+    Move {
+        from: mirror(mv.from),
+        to: mirror(mv.to),
+        ..mv
+    }
+}
+
+fn sorted_uci(moves: impl IntoIterator<Item = Move>) -> Vec<String> {
+    let mut moves: Vec<_> = moves.into_iter().map(|mv| mv.uci()).collect();
+    moves.sort();
+    moves
+}
+
+/// Checks that the colour swapped position has the mirrored legal moves,
+/// and that the bare move and capture masks of each side mirror the other
+/// side's.
+pub fn check_moves(board: &Board) -> Result<(), String> {
+    let swapped = board.swap_colors();
+    let fen = board.fen();
+
+    let expected = sorted_uci(board.legal_moves().into_iter().map(mirrored));
+    let actual = sorted_uci(swapped.legal_moves());
+    if expected != actual {
+        return Err(format!(
+            "{fen}: the colour swapped position has moves {actual:?}, expected {expected:?}"
+        ));
+    }
+
+    let masks: [(&str, Mask, Mask); 4] = [
+        ("white_moves", white_moves, black_moves),
+        ("black_moves", black_moves, white_moves),
+        ("white_captures", white_captures, black_captures),
+        ("black_captures", black_captures, white_captures),
+    ];
+    for (name, mask, swapped_mask) in masks {
+        if bitboard_flip_vertical(mask(*board)) != swapped_mask(swapped) {
+            return Err(format!("{fen}: {name} is not mirrored by the other side"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that an evaluation from the side to move's point of view, like
+/// [`evaluate`](crate::eval::evaluate), gives the colour swapped position the
+/// same score.
+pub fn check_eval(board: &Board, evaluate: impl Fn(&Board) -> i32) -> Result<(), String> {
+    let (score, swapped) = (evaluate(board), evaluate(&board.swap_colors()));
+    if score == swapped {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: scored {score}, but {swapped} with the colours swapped",
+            board.fen()
+        ))
+    }
+}
+
+/// Checks that an evaluation from white's point of view, like
+/// [`evaluate_white`](crate::eval::evaluate_white), gives the colour swapped
+/// position the negated score.
+pub fn check_white_eval(board: &Board, evaluate: impl Fn(&Board) -> i32) -> Result<(), String> {
+    check_eval(board, |board| {
+        if board.whites_turn {
+            evaluate(board)
+        } else {
+            -evaluate(board)
+        }
+    })
+}
+
+/// Runs [`check_moves`] and [`check_eval`] on every position in `fens`.
+pub fn check_corpus(fens: &[&str], evaluate: impl Fn(&Board) -> i32) -> Result<(), String> {
+    for fen in fens {
+        let board = Board::from_fen(fen)?;
+        check_moves(&board)?;
+        check_eval(&board, &evaluate)?;
+    }
+
+    Ok(())
+}
+
+/// Panics with the first difference [`check_corpus`] finds in [`CORPUS`],
+/// for use in tests.
+pub fn assert_symmetric(evaluate: impl Fn(&Board) -> i32) {
+    if let Err(error) = check_corpus(CORPUS, evaluate) {
+        panic!("{error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::{evaluate, evaluate_white};
+    use crate::symmetry::*;
+
+    #[test]
+    fn test_corpus() {
+        assert_symmetric(evaluate);
+        for fen in CORPUS {
+            let board = Board::from(*fen);
+            assert_eq!(check_white_eval(&board, evaluate_white), Ok(()));
+            assert!(board.swap_colors().validate().is_ok(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_asymmetry_is_caught() {
+        let board = Board::new();
+        assert!(check_eval(&board, |board| board.whites_turn as i32).is_err());
+
+        let board = Board::from("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1");
+        assert!(check_white_eval(&board, evaluate).is_err());
+        assert!(check_eval(&board, |board| evaluate_white(board) + 10).is_err());
+    }
+}
@@ -0,0 +1,1468 @@
+// syzygy endgame tablebases.
+//
+// a table is named after its material, strongest side first (`KQvKR`), and
+// comes in two files: `.rtbw` holds win/draw/loss for every position and
+// `.rtbz` the distance to the next capture or pawn move (dtz) for one side to
+// move. positions are turned into an index by placing the pieces group by
+// group with the board's symmetries folded away, and the values are stored
+// compressed with recursive pairing and canonical huffman codes, in blocks
+// reached through a sparse index.
+//
+// the tables leave out whatever the generator could work out with a short
+// search: positions where a capture wins may hold anything, so probing
+// always tries the captures first. dtz tables also only store one side to
+// move, and nothing for positions where the best move zeroes the clock.
+//
+// this follows the layout read by stockfish's and fathom's probing code.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::ops::Neg;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::attacks::KING_ATTACKS;
+use crate::{Bitboard, Board, Color, Move, Piece, Variant};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// flags of each table part
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+// root moves are ranked by how soon they win, unless the win is certain
+// within the fifty move rule
+const MAX_DTZ: i32 = 1 << 18;
+
+/// A tablebase result for the side to move. Cursed wins and blessed losses
+/// are wins and losses that the fifty move rule turns into draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Result<Wdl, String> {
+        match value {
+            -2 => Ok(Wdl::Loss),
+            -1 => Ok(Wdl::BlessedLoss),
+            0 => Ok(Wdl::Draw),
+            1 => Ok(Wdl::CursedWin),
+            2 => Ok(Wdl::Win),
+            _ => Err(format!("{value} is not a win, draw or loss")),
+        }
+    }
+
+    fn signum(self) -> i32 {
+        (self as i32).signum()
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+// the dtz of the move before a capture or pawn move into a position with
+// this result
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Wdl,
+    Dtz,
+}
+
+impl Kind {
+    fn extension(self) -> &'static str {
+        match self {
+            Kind::Wdl => "rtbw",
+            Kind::Dtz => "rtbz",
+        }
+    }
+
+    fn magic(self) -> [u8; 4] {
+        match self {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        }
+    }
+}
+
+// pieces in the order table names list them
+const NAME_ORDER: [(Piece, char); 6] = [
+    (Piece::King, 'K'),
+    (Piece::Queen, 'Q'),
+    (Piece::Rook, 'R'),
+    (Piece::Bishop, 'B'),
+    (Piece::Knight, 'N'),
+    (Piece::Pawn, 'P'),
+];
+
+fn material(board: &Board, color: Color) -> String {
+    NAME_ORDER
+        .iter()
+        .map(|(piece, c)| {
+            let count = (board.pieces(*piece) & board.side(color)).count_ones();
+            c.to_string().repeat(count as usize)
+        })
+        .collect()
+}
+
+fn name_order(side: &str) -> Vec<usize> {
+    side.chars()
+        .map(|c| NAME_ORDER.iter().position(|(_, n)| *n == c).unwrap_or(6))
+        .collect()
+}
+
+/// The name of the table for `board`'s material, such as `KQvKR`: the side
+/// with more pieces first, or with equal numbers the side with the
+/// stronger ones.
+pub fn table_name(board: &Board) -> String {
+    let white = material(board, Color::White);
+    let black = material(board, Color::Black);
+
+    if (white.len(), name_order(&black)) < (black.len(), name_order(&white)) {
+        format!("{black}v{white}")
+    } else {
+        format!("{white}v{black}")
+    }
+}
+
+// the tables used to turn piece placements into indices
+struct Maps {
+    // squares below the a1-h8 diagonal, 0 to 27
+    b1h1h7: [u64; 64],
+    // the a1-d1-d4 triangle, 0 to 9, with the diagonal last
+    a1d1d4: [u64; 64],
+    // the 462 ways to place two kings, the first in the triangle
+    kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 7],
+    // a2 to h7, higher for the leading pawn: nearer the edge and lower down
+    pawns: [u64; 64],
+    lead_pawn_index: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+static MAPS: OnceLock<Maps> = OnceLock::new();
+
+// positive above the a1-h8 diagonal, negative below it
+fn off_diagonal(square: usize) -> i32 {
+    (square >> 3) as i32 - (square & 7) as i32
+}
+
+fn maps() -> &'static Maps {
+    MAPS.get_or_init(|| {
+        let mut maps = Maps {
+            b1h1h7: [0; 64],
+            a1d1d4: [0; 64],
+            kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 7],
+            pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                maps.b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut code = 0;
+        let mut diagonal = Vec::new();
+        for square in 0..=27 {
+            if square & 7 > 3 {
+                continue;
+            }
+            if off_diagonal(square) < 0 {
+                maps.a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            maps.a1d1d4[square] = code;
+            code += 1;
+        }
+
+        // with the first king on the diagonal, the second one can be kept
+        // on or below it. positions with both on the diagonal come last
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+        for index in 0..10 {
+            for (first, attacks) in KING_ATTACKS.iter().enumerate().take(28) {
+                if first & 7 > 3 || maps.a1d1d4[first] != index as u64 || (index == 0 && first != 1)
+                {
+                    continue;
+                }
+
+                for second in 0..64 {
+                    if (attacks | 1 << first) & 1 << second != 0 {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) > 0 {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((index, second));
+                    } else {
+                        maps.kk[index][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (index, second) in both_on_diagonal {
+            maps.kk[index][second] = code;
+            code += 1;
+        }
+
+        maps.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..7.min(n + 1) {
+                maps.binomial[k][n] = if k > 0 {
+                    maps.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n { maps.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        // 47 squares are left for the other pawns with the leader on a2, two
+        // fewer for every square further in
+        let mut placed = 0;
+        for lead_pawns in 1..6 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let square = file + rank * 8;
+                    if lead_pawns == 1 {
+                        maps.pawns[square] = 47 - placed;
+                        maps.pawns[square ^ 7] = 46 - placed;
+                        placed += 2;
+                    }
+                    maps.lead_pawn_index[lead_pawns][square] = index;
+                    index += maps.binomial[lead_pawns - 1][maps.pawns[square] as usize];
+                }
+                maps.lead_pawns_size[lead_pawns][file] = index;
+            }
+        }
+
+        maps
+    })
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], String> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("the table file is truncated".to_string())
+}
+
+fn byte(data: &[u8], offset: usize) -> Result<u8, String> {
+    Ok(read::<1>(data, offset)?[0])
+}
+
+fn le16(data: &[u8], offset: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes(read(data, offset)?))
+}
+
+fn le32(data: &[u8], offset: usize) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(read(data, offset)?))
+}
+
+fn be32(data: &[u8], offset: usize) -> Result<u32, String> {
+    Ok(u32::from_be_bytes(read(data, offset)?))
+}
+
+fn be64(data: &[u8], offset: usize) -> Result<u64, String> {
+    Ok(u64::from_be_bytes(read(data, offset)?))
+}
+
+// one compressed table: a side to move, and a file of the leading pawn when
+// there are pawns. offsets point into the file
+#[derive(Debug, Clone, Default)]
+struct Pairs {
+    flags: u8,
+    // piece codes as in the file, 1 to 6 for white pawn to king, 9 to 14
+    // for black
+    pieces: [u8; 7],
+    // the number of pieces in each group, zero terminated, and what each
+    // group's index is multiplied by. the last factor is the table size
+    group_len: [usize; 8],
+    group_index: [u64; 8],
+
+    block_size: usize,
+    span: u64,
+    sparse_index_size: usize,
+    blocks: usize,
+    block_lengths_size: usize,
+    min_sym_len: u8,
+    lowest_sym: usize,
+    base64: Vec<u64>,
+    sym_len: Vec<u32>,
+    btree: usize,
+
+    sparse_index: usize,
+    block_lengths: usize,
+    data: usize,
+    // where the dtz values for each result start, for mapped dtz tables
+    map: [usize; 4],
+}
+
+impl Pairs {
+    fn left(&self, data: &[u8], sym: usize) -> Result<usize, String> {
+        let [a, b, _] = read::<3>(data, self.btree + 3 * sym)?;
+        Ok(((b as usize & 0xf) << 8) | a as usize)
+    }
+
+    fn right(&self, data: &[u8], sym: usize) -> Result<usize, String> {
+        let [_, b, c] = read::<3>(data, self.btree + 3 * sym)?;
+        Ok(((c as usize) << 4) | (b as usize >> 4))
+    }
+
+    fn sym_len(&self, sym: usize) -> Result<i64, String> {
+        self.sym_len
+            .get(sym)
+            .map(|len| *len as i64)
+            .ok_or(format!("symbol {sym} is out of range"))
+    }
+
+    fn block_length(&self, data: &[u8], block: usize) -> Result<i64, String> {
+        if block >= self.block_lengths_size {
+            return Err(format!("block {block} is out of range"));
+        }
+        Ok(le16(data, self.block_lengths + 2 * block)? as i64)
+    }
+
+    // the number of values a symbol stands for, less one. a symbol is
+    // either a value or a pair of symbols
+    fn set_sym_len(
+        &mut self,
+        data: &[u8],
+        sym: usize,
+        visited: &mut [bool],
+    ) -> Result<u32, String> {
+        visited[sym] = true;
+
+        let right = self.right(data, sym)?;
+        if right == 0xfff {
+            return Ok(0);
+        }
+
+        let left = self.left(data, sym)?;
+        for child in [left, right] {
+            if child >= visited.len() {
+                return Err(format!("symbol {child} is out of range"));
+            }
+            if !visited[child] {
+                self.sym_len[child] = self.set_sym_len(data, child, visited)?;
+            }
+        }
+
+        Ok(self.sym_len[left] + self.sym_len[right] + 1)
+    }
+
+    // reads the block layout and the huffman code, returning where the next
+    // table's starts
+    fn set_sizes(&mut self, data: &[u8], mut offset: usize) -> Result<usize, String> {
+        self.flags = byte(data, offset)?;
+        offset += 1;
+
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_sym_len = byte(data, offset)?;
+            return Ok(offset + 1);
+        }
+
+        let groups = self.group_len.iter().position(|len| *len == 0).unwrap();
+        let size = self.group_index[groups];
+
+        self.block_size = 1 << byte(data, offset)?;
+        self.span = 1 << byte(data, offset + 1)?;
+        self.sparse_index_size = size.div_ceil(self.span) as usize;
+        let padding = byte(data, offset + 2)? as usize;
+        self.blocks = le32(data, offset + 3)? as usize;
+        self.block_lengths_size = self.blocks + padding;
+        let max_sym_len = byte(data, offset + 7)?;
+        self.min_sym_len = byte(data, offset + 8)?;
+        offset += 9;
+
+        if self.min_sym_len == 0 || max_sym_len < self.min_sym_len || max_sym_len > 32 {
+            return Err("the table has a broken huffman code".to_string());
+        }
+
+        // the code is canonical, with longer codes having lower values. base64
+        // holds the lowest code of each length, padded to 64 bits
+        self.lowest_sym = offset;
+        let lengths = (max_sym_len - self.min_sym_len) as usize + 1;
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = le16(data, offset + 2 * i)? as u64;
+            let next = le16(data, offset + 2 * (i + 1))? as u64;
+            self.base64[i] = (self.base64[i + 1].wrapping_add(lowest).wrapping_sub(next)) / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base <<= 64 - i - self.min_sym_len as usize;
+        }
+        offset += 2 * lengths;
+
+        let symbols = le16(data, offset)? as usize;
+        offset += 2;
+        self.btree = offset;
+        self.sym_len = vec![0; symbols];
+
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.sym_len[sym] = self.set_sym_len(data, sym, &mut visited)?;
+            }
+        }
+
+        Ok(offset + 3 * symbols + (symbols & 1))
+    }
+
+    // the value stored at `index`
+    fn decompress(&self, data: &[u8], index: u64) -> Result<usize, String> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Ok(self.min_sym_len as usize);
+        }
+
+        // the sparse index gives the block and offset of every span's middle
+        // value, from which the blocks are walked to the one holding ours
+        let entry = self.sparse_index + 6 * (index / self.span) as usize;
+        let mut block = le32(data, entry)? as usize;
+        let mut offset = le16(data, entry + 4)? as i64;
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+
+        while offset < 0 {
+            block = block
+                .checked_sub(1)
+                .ok_or("the sparse index is broken".to_string())?;
+            offset += self.block_length(data, block)? + 1;
+        }
+        while offset > self.block_length(data, block)? {
+            offset -= self.block_length(data, block)? + 1;
+            block += 1;
+        }
+
+        // decode symbols until reaching the one that covers the offset
+        let mut pointer = self.data + block * self.block_size;
+        let mut buffer = be64(data, pointer)?;
+        let mut buffer_size = 64;
+        pointer += 8;
+
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buffer < self.base64[len] {
+                len += 1;
+            }
+
+            let shift = 64 - len - self.min_sym_len as usize;
+            sym = (((buffer - self.base64[len]) >> shift) as u16)
+                .wrapping_add(le16(data, self.lowest_sym + 2 * len)?) as usize;
+
+            if offset < self.sym_len(sym)? + 1 {
+                break;
+            }
+
+            offset -= self.sym_len(sym)? + 1;
+            len += self.min_sym_len as usize;
+            buffer <<= len;
+            buffer_size -= len;
+
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= (be32(data, pointer)? as u64) << (64 - buffer_size);
+                pointer += 4;
+            }
+        }
+
+        // then expand the symbol's pairs down to the value
+        while self.sym_len(sym)? != 0 {
+            let left = self.left(data, sym)?;
+            if offset < self.sym_len(left)? + 1 {
+                sym = left;
+            } else {
+                offset -= self.sym_len(left)? + 1;
+                sym = self.right(data, sym)?;
+            }
+        }
+
+        self.left(data, sym)
+    }
+}
+
+struct Table {
+    kind: Kind,
+    data: Vec<u8>,
+    // the first side's material in the name, the one stored as white
+    key: String,
+    symmetric: bool,
+    has_pawns: bool,
+    // whether some piece other than a king is the only one of its kind
+    has_unique_pieces: bool,
+    piece_count: usize,
+    // pawns of the leading colour, which has fewer, and of the other
+    pawn_count: [usize; 2],
+    // indexed by side to move (wdl only) and the leading pawn's file
+    pairs: Vec<Vec<Pairs>>,
+}
+
+impl Table {
+    fn new(name: &str, kind: Kind, data: Vec<u8>) -> Result<Table, String> {
+        let (white, black) = name
+            .split_once('v')
+            .ok_or(format!("{name} is not a table name"))?;
+
+        let count = |side: &str, c: char| side.chars().filter(|p| *p == c).count();
+        let has_unique_pieces = ['Q', 'R', 'B', 'N', 'P']
+            .into_iter()
+            .any(|c| count(white, c) == 1 || count(black, c) == 1);
+
+        // the side with fewer pawns leads, when both have some
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        let pawn_count = if black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns) {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        let mut table = Table {
+            kind,
+            data,
+            key: white.to_string(),
+            symmetric: white == black,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            piece_count: white.len() + black.len(),
+            pawn_count,
+            pairs: Vec::new(),
+        };
+        table.read_layout()?;
+        Ok(table)
+    }
+
+    fn pairs(&self, stm: usize, file: usize) -> &Pairs {
+        let side = &self.pairs[stm % self.pairs.len()];
+        &side[if self.has_pawns { file } else { 0 }]
+    }
+
+    fn read_layout(&mut self) -> Result<(), String> {
+        let data = &self.data;
+        if read::<4>(data, 0)? != self.kind.magic() {
+            return Err("not a syzygy table of the right kind".to_string());
+        }
+        if (byte(data, 4)? & 2 != 0) != self.has_pawns {
+            return Err("the table doesn't match its name".to_string());
+        }
+
+        let sides = if self.kind == Kind::Wdl && !self.symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+
+        let mut pairs = vec![vec![Pairs::default(); files]; sides];
+        let mut offset = 5;
+
+        for file in 0..files {
+            let first = byte(data, offset)?;
+            let second = if both_pawns {
+                byte(data, offset + 1)?
+            } else {
+                0xff
+            };
+            let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            offset += 1 + both_pawns as usize;
+
+            for k in 0..self.piece_count {
+                let pieces = byte(data, offset)?;
+                for (side, pairs) in pairs.iter_mut().enumerate() {
+                    pairs[file].pieces[k] = if side == 0 { pieces & 0xf } else { pieces >> 4 };
+                }
+                offset += 1;
+            }
+
+            for (side, pairs) in pairs.iter_mut().enumerate() {
+                self.set_groups(&mut pairs[file], order[side], file);
+            }
+        }
+        offset += offset & 1;
+
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                offset = side[file].set_sizes(data, offset)?;
+            }
+        }
+
+        if self.kind == Kind::Dtz {
+            let map = offset;
+            for pairs in pairs[0].iter_mut() {
+                if pairs.flags & MAPPED == 0 {
+                    continue;
+                }
+
+                for start in pairs.map.iter_mut() {
+                    if pairs.flags & WIDE != 0 {
+                        offset += offset & 1;
+                        *start = offset + 2;
+                        offset += 2 * le16(data, offset)? as usize + 2;
+                    } else {
+                        *start = offset + 1;
+                        offset += byte(data, offset)? as usize + 1;
+                    }
+                }
+            }
+            debug_assert!(offset >= map);
+            offset += offset & 1;
+        }
+
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                side[file].sparse_index = offset;
+                offset += 6 * side[file].sparse_index_size;
+            }
+        }
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                side[file].block_lengths = offset;
+                offset += 2 * side[file].block_lengths_size;
+            }
+        }
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                let pairs = &mut side[file];
+                offset = (offset + 0x3f) & !0x3f;
+                pairs.data = offset;
+                offset += pairs.blocks * pairs.block_size;
+
+                if pairs.blocks > 0 && offset > data.len() {
+                    return Err("the table file is truncated".to_string());
+                }
+            }
+        }
+
+        self.pairs = pairs;
+        Ok(())
+    }
+
+    // splits the pieces into the groups they are placed in, and works out
+    // the factor of each group's index from the order the file gives them
+    fn set_groups(&self, pairs: &mut Pairs, order: [u8; 2], file: usize) {
+        let maps = maps();
+
+        let mut first_len: i32 = if self.has_pawns {
+            0
+        } else if self.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+
+        let mut n = 0;
+        pairs.group_len[0] = 1;
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || pairs.pieces[i] == pairs.pieces[i - 1] {
+                pairs.group_len[n] += 1;
+            } else {
+                n += 1;
+                pairs.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        pairs.group_len[n] = 0;
+
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares =
+            64 - pairs.group_len[0] - if both_pawns { pairs.group_len[1] } else { 0 };
+        let mut index = 1u64;
+
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                pairs.group_index[0] = index;
+                index *= if self.has_pawns {
+                    maps.lead_pawns_size[pairs.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                pairs.group_index[1] = index;
+                index *= maps.binomial[pairs.group_len[1]][48 - pairs.group_len[0]];
+            } else {
+                pairs.group_index[next] = index;
+                index *= maps.binomial[pairs.group_len[next]][free_squares];
+                free_squares -= pairs.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+
+        pairs.group_index[n] = index;
+    }
+
+    // the raw value stored for `board`, or `None` for a dtz table that
+    // stores the other side to move
+    fn probe(&self, board: &Board) -> Result<Option<(usize, usize)>, String> {
+        let maps = maps();
+
+        // the table stores the first side of its name as white, and symmetric
+        // tables only store white to move
+        let flip =
+            (self.symmetric && !board.whites_turn) || material(board, Color::White) != self.key;
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let stm = (flip == board.whites_turn) as usize;
+
+        let mut squares = Vec::with_capacity(7);
+        let mut pieces = Vec::with_capacity(7);
+        let mut lead_pawns = 0;
+        let mut file = 0;
+
+        // the leading pawn is the one furthest toward the edge and then
+        // nearest its own side
+        if self.has_pawns {
+            let code = self.pairs[0][0].pieces[0] ^ flip_color;
+            let color = if code & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            lead_pawns = board.pieces(Piece::Pawn) & board.side(color);
+            for square in Bitboard(lead_pawns) {
+                squares.push(square.index() as usize ^ flip_squares);
+                pieces.push(code);
+            }
+
+            let lead = (0..squares.len())
+                .rev()
+                .max_by_key(|i| maps.pawns[squares[*i]])
+                .unwrap_or(0);
+            squares.swap(0, lead);
+
+            let lead_file = squares[0] & 7;
+            file = lead_file.min(7 - lead_file);
+        }
+        let lead_count = squares.len();
+
+        // symmetric tables without pawns can be flipped to either side
+        let one_sided = self.has_pawns || !self.symmetric;
+        if self.kind == Kind::Dtz && one_sided && (self.pairs(0, file).flags & STM) as usize != stm
+        {
+            return Ok(None);
+        }
+
+        let occupied = board.side(Color::White) | board.side(Color::Black);
+        for square in Bitboard(occupied ^ lead_pawns) {
+            let Some((piece, color)) = board.piece_at(square) else {
+                continue;
+            };
+            squares.push(square.index() as usize ^ flip_squares);
+            pieces.push((piece as u8 + 1 + 8 * color as u8) ^ flip_color);
+        }
+
+        let pairs = self.pairs(stm, file);
+        let size = squares.len();
+        if size != self.piece_count {
+            return Err("the position doesn't match the table".to_string());
+        }
+
+        // put the pieces in the table's order
+        for i in lead_count..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|j| pairs.pieces[i] == pieces[*j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        if pieces[..size] != pairs.pieces[..size] {
+            return Err("the position doesn't match the table".to_string());
+        }
+
+        // mirror the leading piece onto the a to d files
+        if squares[0] & 7 > 3 {
+            for square in &mut squares {
+                *square ^= 7;
+            }
+        }
+
+        let mut index;
+        if self.has_pawns {
+            index = maps.lead_pawn_index[lead_count][squares[0]];
+            squares[1..lead_count].sort_by_key(|square| maps.pawns[*square]);
+            for (i, square) in squares[1..lead_count].iter().enumerate() {
+                index += maps.binomial[i + 1][maps.pawns[*square] as usize];
+            }
+        } else {
+            // without pawns the board can also be flipped vertically and
+            // along the diagonal, so the leading piece ends up in a1-d1-d4
+            if squares[0] >> 3 > 3 {
+                for square in &mut squares {
+                    *square ^= 56;
+                }
+            }
+
+            for i in 0..pairs.group_len[0] {
+                let off = off_diagonal(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for square in &mut squares[i..] {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            index = if self.has_unique_pieces {
+                unique_index(maps, &squares)
+            } else {
+                maps.kk[maps.a1d1d4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        // then every further group, by the squares left for it
+        index *= pairs.group_index[0];
+        let mut start = pairs.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+
+        while pairs.group_len[next] != 0 {
+            let len = pairs.group_len[next];
+            squares[start..start + len].sort_unstable();
+
+            let mut n = 0;
+            for i in 0..len {
+                let square = squares[start + i];
+                let adjust = squares[..start].iter().filter(|s| square > **s).count();
+                let available = square - adjust - if remaining_pawns { 8 } else { 0 };
+                n += maps.binomial[i + 1][available];
+            }
+
+            remaining_pawns = false;
+            index += n * pairs.group_index[next];
+            start += len;
+            next += 1;
+        }
+
+        Ok(Some((pairs.decompress(&self.data, index)?, file)))
+    }
+
+    // turns a raw dtz value into plies
+    fn dtz_value(&self, value: usize, file: usize, wdl: Wdl) -> Result<i32, String> {
+        let pairs = self.pairs(0, file);
+        let flags = pairs.flags;
+
+        let mut value = value as i32;
+        if flags & MAPPED != 0 {
+            let map = pairs.map[match wdl {
+                Wdl::Win => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3,
+                Wdl::Draw => return Err("dtz tables don't store draws".to_string()),
+            }];
+            value = if flags & WIDE != 0 {
+                le16(&self.data, map + 2 * value as usize)? as i32
+            } else {
+                byte(&self.data, map + value as usize)? as i32
+            };
+        }
+
+        // tables count moves rather than plies unless they say otherwise
+        if (wdl == Wdl::Win && flags & WIN_PLIES == 0)
+            || (wdl == Wdl::Loss && flags & LOSS_PLIES == 0)
+            || wdl == Wdl::CursedWin
+            || wdl == Wdl::BlessedLoss
+        {
+            value *= 2;
+        }
+
+        Ok(value + 1)
+    }
+}
+
+// the leading group of three different pieces, folded into the a1-d1-d4
+// triangle and then below the diagonal
+fn unique_index(maps: &Maps, squares: &[usize]) -> u64 {
+    let (s0, s1, s2) = (squares[0], squares[1], squares[2]);
+    let adjust1 = (s1 > s0) as usize;
+    let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+    let rank = |square: usize| (square >> 3) as u64;
+
+    if off_diagonal(s0) != 0 {
+        (maps.a1d1d4[s0] * 63 + (s1 - adjust1) as u64) * 62 + (s2 - adjust2) as u64
+    } else if off_diagonal(s1) != 0 {
+        (6 * 63 + rank(s0) * 28 + maps.b1h1h7[s1]) * 62 + (s2 - adjust2) as u64
+    } else if off_diagonal(s2) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(s0) * 7 * 28
+            + (rank(s1) - adjust1 as u64) * 28
+            + maps.b1h1h7[s2]
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + rank(s0) * 7 * 6
+            + (rank(s1) - adjust1 as u64) * 6
+            + (rank(s2) - adjust2 as u64)
+    }
+}
+
+/// A directory of syzygy tables, opened as they are needed.
+pub struct Tablebases {
+    dir: PathBuf,
+    max_pieces: usize,
+    tables: Mutex<HashMap<(String, Kind), Arc<Table>>>,
+}
+
+impl fmt::Debug for Tablebases {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebases")
+            .field("dir", &self.dir)
+            .field("max_pieces", &self.max_pieces)
+            .finish()
+    }
+}
+
+impl Tablebases {
+    /// Looks for `.rtbw` and `.rtbz` files in `dir`. Positions with up to
+    /// as many pieces as the largest table found can be probed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Tablebases, String> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+
+        let mut max_pieces = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_table = path
+                .extension()
+                .is_some_and(|ext| ext == Kind::Wdl.extension() || ext == Kind::Dtz.extension());
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if is_table && name.split_once('v').is_some() {
+                max_pieces = max_pieces.max(name.len() - 1);
+            }
+        }
+
+        Ok(Tablebases {
+            dir: dir.to_path_buf(),
+            max_pieces,
+            tables: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The most pieces, kings included, of any table in the directory.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    fn table(&self, board: &Board, kind: Kind) -> Result<Arc<Table>, String> {
+        let name = table_name(board);
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(table) = tables.get(&(name.clone(), kind)) {
+            return Ok(table.clone());
+        }
+
+        let path = self.dir.join(format!("{name}.{}", kind.extension()));
+        let data = fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let table = Arc::new(Table::new(&name, kind, data).map_err(|e| format!("{name}: {e}"))?);
+
+        tables.insert((name, kind), table.clone());
+        Ok(table)
+    }
+
+    fn check(&self, board: &Board) -> Result<(), String> {
+        let pieces = (board.side(Color::White) | board.side(Color::Black)).count_ones() as usize;
+        if board.variant != Variant::Standard {
+            Err("tablebases are only for standard chess".to_string())
+        } else if !board.castling.is_empty() {
+            Err("tablebases don't cover positions where castling is possible".to_string())
+        } else if pieces > self.max_pieces.max(2) {
+            Err(format!(
+                "there are no tables with {pieces} pieces, only up to {}",
+                self.max_pieces
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn wdl_table(&self, board: &Board) -> Result<Wdl, String> {
+        if (board.side(Color::White) | board.side(Color::Black)).count_ones() == 2 {
+            return Ok(Wdl::Draw);
+        }
+
+        let table = self.table(board, Kind::Wdl)?;
+        let (value, _) = table.probe(board)?.unwrap();
+        Wdl::from_value(value as i32 - 2)
+    }
+
+    // the result with captures tried first, as the tables may hold anything
+    // where a capture is best. also whether the best move zeroes the clock,
+    // counting pawn moves with `zeroing`
+    fn search(&self, board: &Board, zeroing: bool) -> Result<(Wdl, bool), String> {
+        let moves = board.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+
+        for mv in &moves {
+            let pawn = board.piece_on(mv.from) == Some(Piece::Pawn);
+            let searched_move = board.is_capture(*mv) || (zeroing && pawn);
+            if !searched_move {
+                continue;
+            }
+
+            searched += 1;
+            let Some(child) = board.play(*mv) else {
+                continue;
+            };
+            let value = -self.search(&child, false)?.0;
+
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Ok((value, true));
+                }
+            }
+        }
+
+        // with nothing but captures to play, the table may not hold the
+        // right value (en passant positions, for one)
+        let only_captures = searched > 0 && searched == moves.len();
+        let value = if only_captures {
+            best
+        } else {
+            self.wdl_table(board)?
+        };
+
+        if best >= value {
+            Ok((best, best > Wdl::Draw || only_captures))
+        } else {
+            Ok((value, false))
+        }
+    }
+
+    fn dtz(&self, board: &Board) -> Result<i32, String> {
+        let (wdl, zeroing) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+        if zeroing {
+            return Ok(dtz_before_zeroing(wdl));
+        }
+
+        let table = self.table(board, Kind::Dtz)?;
+        if let Some((value, file)) = table.probe(board)? {
+            let dtz = table.dtz_value(value, file, wdl)?;
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Ok((dtz + if cursed { 100 } else { 0 }) * wdl.signum());
+        }
+
+        // the table only has the other side to move, so look one move ahead
+        // for the quickest win, or the slowest loss
+        let mut best = 0xffff;
+        for mv in board.legal_moves() {
+            let zeroing = board.is_capture(mv) || board.piece_on(mv.from) == Some(Piece::Pawn);
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+
+            let mut dtz = if zeroing {
+                -dtz_before_zeroing(self.search(&child, false)?.0)
+            } else {
+                -self.dtz(&child)?
+            };
+
+            if dtz == 1 && child.in_check() && child.legal_moves().is_empty() {
+                best = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < best && dtz.signum() == wdl.signum() {
+                best = dtz;
+            }
+        }
+
+        Ok(if best == 0xffff { -1 } else { best })
+    }
+
+    /// Whether the side to move wins, draws or loses with perfect play.
+    pub fn probe_wdl(&self, board: &Board) -> Result<Wdl, String> {
+        self.check(board)?;
+        Ok(self.search(board, false)?.0)
+    }
+
+    /// The number of plies to the next capture or pawn move with perfect
+    /// play, positive when the side to move wins and negative when it
+    /// loses. A draw is 0. Cursed wins and blessed losses are 100 further
+    /// from zero than their true distance.
+    pub fn probe_dtz(&self, board: &Board) -> Result<i32, String> {
+        self.check(board)?;
+        self.dtz(board)
+    }
+
+    /// The legal moves that keep the best result, as far as the fifty move
+    /// rule allows. Every move that wins in time is kept, so the search can
+    /// pick among them; otherwise only the moves that get furthest.
+    pub fn root_moves(&self, board: &Board) -> Result<Vec<Move>, String> {
+        self.check(board)?;
+
+        let clock = board.halfmove_clock as i32;
+        let mut ranked = Vec::new();
+
+        for mv in board.legal_moves() {
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+
+            // the dtz from the root, counting this move
+            let mut dtz = if child.halfmove_clock == 0 {
+                dtz_before_zeroing(-self.search(&child, false)?.0)
+            } else if child.halfmove_clock >= 100 {
+                0
+            } else {
+                let dtz = -self.dtz(&child)?;
+                dtz + dtz.signum()
+            };
+
+            if dtz == 2 && child.in_check() && child.legal_moves().is_empty() {
+                dtz = 1;
+            }
+
+            let rank = if dtz > 0 {
+                if dtz + clock <= 99 {
+                    MAX_DTZ
+                } else {
+                    MAX_DTZ - (dtz + clock)
+                }
+            } else if dtz < 0 {
+                if -dtz * 2 + clock < 100 {
+                    -MAX_DTZ
+                } else {
+                    -MAX_DTZ + (-dtz + clock)
+                }
+            } else {
+                0
+            };
+
+            ranked.push((mv, rank));
+        }
+
+        let best = ranked.iter().map(|(_, rank)| *rank).max();
+        Ok(ranked
+            .into_iter()
+            .filter(|(_, rank)| Some(*rank) == best)
+            .map(|(mv, _)| mv)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syzygy::*;
+
+    // a table that holds a single value for each side to move, which is
+    // enough to go through the file layout and the indexing
+    fn single_value_table(kind: Kind, pieces: &[u8], values: &[(u8, u8)]) -> Vec<u8> {
+        let mut data = kind.magic().to_vec();
+        data.push(if values.len() == 2 { 1 } else { 0 });
+        data.push(0);
+        data.extend(pieces.iter().map(|piece| piece | piece << 4));
+        if data.len() & 1 == 1 {
+            data.push(0);
+        }
+        for (flags, value) in values {
+            data.extend([SINGLE_VALUE | flags, *value]);
+        }
+        data
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chessboard-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_table_names() {
+        assert_eq!(
+            table_name(&Board::from("8/8/8/8/8/8/8/K1k1q3 w - - 0 1")),
+            "KQvK"
+        );
+        assert_eq!(
+            table_name(&Board::from("8/8/8/8/8/8/2R5/K1k1q3 w - - 0 1")),
+            "KQvKR"
+        );
+        assert_eq!(
+            table_name(&Board::from("8/5p2/8/8/8/8/2PR4/K1k1r3 w - - 0 1")),
+            "KRPvKRP"
+        );
+        assert_eq!(
+            table_name(&Board::from("8/8/8/8/8/8/2PN4/K1k1q3 w - - 0 1")),
+            "KNPvKQ"
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        let maps = maps();
+        let kk = maps.kk.iter().flatten().max().unwrap();
+        assert_eq!(*kk, 461);
+        assert_eq!(maps.binomial[2][62], 1891);
+        assert_eq!(maps.pawns[crate::Square::A2.index() as usize], 47);
+        assert_eq!(maps.pawns[crate::Square::H2.index() as usize], 46);
+        // one pawn can go on any of the six ranks of its file
+        assert_eq!(maps.lead_pawns_size[1], [6, 6, 6, 6]);
+    }
+
+    #[test]
+    fn test_decompress() {
+        // four two bit symbols: the values 0 and 1, the pair 0 1 and that
+        // pair twice
+        let btree: [(usize, usize); 4] = [(0, 0xfff), (1, 0xfff), (0, 1), (2, 2)];
+        let expand = |sym: usize| match sym {
+            0 => vec![0],
+            1 => vec![1],
+            2 => vec![0, 1],
+            _ => vec![0, 1, 0, 1],
+        };
+
+        // a pseudo random message in blocks of 100 symbols
+        let mut seed = 1u32;
+        let blocks: Vec<Vec<usize>> = (0..20)
+            .map(|_| {
+                (0..100)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                        (seed >> 16) as usize % 4
+                    })
+                    .collect()
+            })
+            .collect();
+        let lengths: Vec<usize> = blocks
+            .iter()
+            .map(|block| block.iter().map(|sym| expand(*sym).len()).sum())
+            .collect();
+        let values: Vec<usize> = blocks.iter().flatten().flat_map(|s| expand(*s)).collect();
+
+        // 32 byte blocks, a sparse entry every 64 values, one block of padding
+        let mut data = vec![0, 5, 6, 1];
+        data.extend((blocks.len() as u32).to_le_bytes());
+        data.extend([2, 2, 0, 0, 4, 0]);
+        for (left, right) in btree {
+            data.extend([
+                left as u8,
+                (left >> 8) as u8 | (right << 4) as u8,
+                (right >> 4) as u8,
+            ]);
+        }
+
+        let mut pairs = Pairs::default();
+        pairs.group_len[0] = 1;
+        pairs.group_index[1] = values.len() as u64;
+        assert_eq!(pairs.set_sizes(&data, 0), Ok(data.len()));
+        assert_eq!(pairs.sym_len, vec![0, 0, 1, 3]);
+
+        let starts: Vec<usize> = lengths
+            .iter()
+            .scan(0, |start, len| {
+                *start += len;
+                Some(*start - len)
+            })
+            .collect();
+        pairs.sparse_index = data.len();
+        for k in 0..pairs.sparse_index_size {
+            let middle = k * 64 + 32;
+            let block = starts
+                .iter()
+                .rposition(|start| *start <= middle.min(values.len() - 1))
+                .unwrap();
+            data.extend((block as u32).to_le_bytes());
+            data.extend(((middle - starts[block]) as u16).to_le_bytes());
+        }
+
+        pairs.block_lengths = data.len();
+        for len in lengths.iter().chain([&1]) {
+            data.extend(((len - 1) as u16).to_le_bytes());
+        }
+
+        pairs.data = data.len();
+        for block in &blocks {
+            let mut bytes = [0u8; 32];
+            for (i, sym) in block.iter().enumerate() {
+                bytes[i / 4] |= (*sym as u8) << (6 - 2 * (i % 4));
+            }
+            data.extend(bytes);
+        }
+        data.extend([0; 8]);
+
+        for (index, value) in values.iter().enumerate() {
+            assert_eq!(pairs.decompress(&data, index as u64), Ok(*value));
+        }
+    }
+
+    #[test]
+    fn test_missing_tables() {
+        let dir = test_dir("empty");
+        let tablebases = Tablebases::open(&dir).unwrap();
+        assert_eq!(tablebases.max_pieces(), 0);
+
+        // two kings are a draw without any table
+        let board = Board::from("8/8/8/8/8/8/8/K1k5 w - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Draw));
+        assert_eq!(tablebases.probe_dtz(&board), Ok(0));
+
+        let board = Board::from("8/8/8/8/8/8/8/K1k1q3 w - - 0 1");
+        assert!(tablebases.probe_wdl(&board).is_err());
+        assert!(Tablebases::open(dir.join("missing")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_single_value_tables() {
+        let dir = test_dir("kqvk");
+        // kings and queen as one group, white to move wins and black loses
+        fs::write(
+            dir.join("KQvK.rtbw"),
+            single_value_table(Kind::Wdl, &[6, 5, 14], &[(0, 4), (0, 0)]),
+        )
+        .unwrap();
+        // white to move is 5 moves from zeroing, black to move isn't stored
+        fs::write(
+            dir.join("KQvK.rtbz"),
+            single_value_table(Kind::Dtz, &[6, 5, 14], &[(0, 5)]),
+        )
+        .unwrap();
+
+        let tablebases = Tablebases::open(&dir).unwrap();
+        assert_eq!(tablebases.max_pieces(), 3);
+
+        let board = Board::from("8/8/8/3k4/8/8/8/KQ6 w - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Win));
+        assert_eq!(tablebases.probe_dtz(&board), Ok(11));
+
+        // black is the stronger side, so the table is read with colours
+        // swapped
+        let board = Board::from("kq6/8/8/8/3K4/8/8/8 w - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Loss));
+
+        // every black move leads to white to move, 11 plies from zeroing
+        let board = Board::from("8/8/8/3k4/8/8/8/KQ6 b - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Loss));
+        assert_eq!(tablebases.probe_dtz(&board), Ok(-12));
+
+        // but the queen can be taken
+        let board = Board::from("8/8/8/8/8/8/1k6/1Q5K b - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Draw));
+        assert_eq!(
+            tablebases.root_moves(&board),
+            Ok(vec![crate::Move::new(
+                crate::Square::B2,
+                crate::Square::B1,
+                None
+            )])
+        );
+
+        // the table doesn't know about more pieces
+        let board = Board::from("8/8/8/3k4/8/8/8/KQQ5 w - - 0 1");
+        assert!(tablebases.probe_wdl(&board).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    // probes the real 3 and 4 piece tables in tests/syzygy, or in
+    // SYZYGY_PATH if it is set. a missing table is a failure, not a skip
+    #[test]
+    fn test_real_tables() {
+        let dir = std::env::var("SYZYGY_PATH")
+            .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy").to_string());
+        let missing: Vec<_> = ["KQvK", "KRvK", "KNvK", "KPvK", "KRvKR"]
+            .iter()
+            .flat_map(|table| [format!("{table}.rtbw"), format!("{table}.rtbz")])
+            .filter(|file| !Path::new(&dir).join(file).is_file())
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "missing from {dir}: {}",
+            missing.join(", ")
+        );
+
+        let tablebases = Tablebases::open(dir).unwrap();
+        assert!(tablebases.max_pieces() >= 4);
+
+        let cases = [
+            ("8/8/8/8/8/8/3k4/KQ6 w - - 0 1", Wdl::Win),
+            ("8/8/8/8/8/8/8/KQ1k4 b - - 0 1", Wdl::Loss),
+            ("8/8/8/8/8/8/3k4/KR6 w - - 0 1", Wdl::Win),
+            ("8/8/8/8/8/2k5/8/KN6 w - - 0 1", Wdl::Draw),
+            ("8/8/8/8/8/8/4P3/k1K5 w - - 0 1", Wdl::Win),
+            ("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1", Wdl::Draw),
+            ("4k3/7r/8/8/8/8/8/R3K3 w - - 0 1", Wdl::Draw),
+        ];
+        for (fen, wdl) in cases {
+            assert_eq!(tablebases.probe_wdl(&Board::from(fen)), Ok(wdl), "{fen}");
+        }
+
+        // mates in one, for either colour
+        for fen in [
+            "3k4/8/3K4/8/8/8/8/7Q w - - 0 1",
+            "3k4/8/3K4/8/8/8/8/7R w - - 0 1",
+            "7q/8/8/8/8/3k4/8/3K4 b - - 0 1",
+        ] {
+            assert_eq!(tablebases.probe_dtz(&Board::from(fen)), Ok(1), "{fen}");
+        }
+
+        // taking the queen is the only move that doesn't lose
+        let board = Board::from("8/8/8/8/8/8/1k6/1Q5K b - - 0 1");
+        assert_eq!(tablebases.probe_wdl(&board), Ok(Wdl::Draw));
+        assert_eq!(
+            tablebases.root_moves(&board),
+            Ok(vec![crate::Move::new(
+                crate::Square::B2,
+                crate::Square::B1,
+                None
+            )])
+        );
+    }
+}
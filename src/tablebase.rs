@@ -0,0 +1,818 @@
+// endgame tables of our own, for the variants syzygy has nothing for.
+//
+// a table covers one material balance. its index runs over where each piece
+// stands, the first piece kept to the a to d files when it has no twin (the
+// rest of the board is mirrored to match), with identical pieces in
+// ascending order so a position has exactly one index.
+//
+// generation works backwards from the positions the game is over in. the
+// positions mated (or otherwise lost) at ply d make every position that can
+// move into them a win at d + 1; a position whose every move leads to a win
+// for the other side is lost, one ply after the longest of them. the moves
+// that leave the table, captures and promotions, are looked up in smaller
+// tables, which are generated first. anything undecided at the end is a
+// draw.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::attacks::{KING_ATTACKS, KNIGHT_ATTACKS};
+use crate::endgame::Material;
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::{Bitboard, Board, CastlingRights, Color, Move, Outcome, Piece, Rank, Square, Variant};
+
+/// The most pieces, kings included, a table is generated for. The index
+/// doesn't fold the two kings together, so five pieces would take 2 × 32 ×
+/// 64⁴ entries, several gigabytes while generating.
+pub const MAX_PIECES: usize = 4;
+
+const MAGIC: [u8; 4] = *b"DTM1";
+
+// table entries: no such position, a draw, and then wins and losses by the
+// number of plies to the end of the game
+const INVALID: u16 = 0;
+const DRAW: u16 = 1;
+
+// children that can't all be wins for the other side, so never a loss
+const BLOCKED: u8 = u8::MAX;
+// decided positions whose predecessors have been seen to. a winning capture
+// and a winning move inside can queue the same position twice
+const DONE: u8 = u8::MAX - 1;
+
+// pieces in the order a table lays them out
+const ORDER: [Piece; 6] = [
+    Piece::King,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+/// A position's value with best play, counted in plies to the end of the
+/// game. `Loss(0)` is a side that has just been mated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dtm {
+    Win(u16),
+    Loss(u16),
+    Draw,
+}
+
+impl Dtm {
+    fn encode(self) -> u16 {
+        match self {
+            Dtm::Win(plies) => 3 + 2 * plies,
+            Dtm::Loss(plies) => 2 + 2 * plies,
+            Dtm::Draw => DRAW,
+        }
+    }
+
+    fn decode(value: u16) -> Option<Dtm> {
+        match value {
+            INVALID => None,
+            DRAW => Some(Dtm::Draw),
+            _ if value % 2 == 1 => Some(Dtm::Win((value - 3) / 2)),
+            _ => Some(Dtm::Loss((value - 2) / 2)),
+        }
+    }
+
+    // the game over on the board, for the side to move
+    fn of_outcome(outcome: Outcome, side_to_move: Color) -> Dtm {
+        match outcome {
+            Outcome::Win(color) if color == side_to_move => Dtm::Win(0),
+            Outcome::Win(_) => Dtm::Loss(0),
+            Outcome::Draw => Dtm::Draw,
+        }
+    }
+}
+
+struct Layout {
+    // each kind of piece with how many of it there are
+    groups: Vec<(Piece, Color, usize)>,
+    pieces: usize,
+    mirror: bool,
+}
+
+impl Layout {
+    fn new(material: &Material) -> Layout {
+        let mut groups = Vec::new();
+        for color in [Color::White, Color::Black] {
+            for piece in ORDER {
+                let count = material.count(color, piece) as usize;
+                if count > 0 {
+                    groups.push((piece, color, count));
+                }
+            }
+        }
+
+        let pieces = groups.iter().map(|(_, _, count)| count).sum();
+        let mirror = groups.first().is_some_and(|(_, _, count)| *count == 1);
+
+        Layout {
+            groups,
+            pieces,
+            mirror,
+        }
+    }
+
+    fn size(&self) -> usize {
+        let first = if self.mirror { 32 } else { 64 };
+        2 * first * 64usize.pow(self.pieces.saturating_sub(1) as u32)
+    }
+
+    // whether `board` is the way round the table stores it
+    fn is_canonical(&self, board: &Board) -> bool {
+        let (piece, color, _) = self.groups[0];
+        !self.mirror
+            || Bitboard(board.pieces(piece) & board.side(color))
+                .iter()
+                .all(|square| square.file().index() < 4)
+    }
+
+    fn index(&self, board: &Board) -> usize {
+        let flip = if self.is_canonical(board) { 0 } else { 7 };
+
+        let mut index = 0;
+        for (i, &(piece, color, _)) in self.groups.iter().enumerate() {
+            let mut squares: Vec<usize> = Bitboard(board.pieces(piece) & board.side(color))
+                .iter()
+                .map(|square| square.index() as usize ^ flip)
+                .collect();
+            squares.sort_unstable();
+
+            for (j, square) in squares.into_iter().enumerate() {
+                index = if i == 0 && j == 0 && self.mirror {
+                    square % 8 + 4 * (square / 8)
+                } else {
+                    index * 64 + square
+                };
+            }
+        }
+
+        2 * index + !board.whites_turn as usize
+    }
+
+    // the position at `index`, if there is one there
+    fn board(&self, index: usize, variant: Variant) -> Option<Board> {
+        let mut squares = vec![0; self.pieces];
+        let mut rest = index / 2;
+        for square in squares.iter_mut().skip(1).rev() {
+            *square = rest % 64;
+            rest /= 64;
+        }
+        squares[0] = if self.mirror {
+            rest % 4 + 8 * (rest / 4)
+        } else {
+            rest
+        };
+
+        let mut board = Board::new();
+        board.castling = CastlingRights::NONE;
+        board.variant = variant;
+        board.whites_turn = index & 1 == 0;
+
+        let mut squares = squares.into_iter();
+        for &(piece, color, count) in &self.groups {
+            let mut previous = None;
+            for square in squares.by_ref().take(count) {
+                if previous.is_some_and(|previous| previous >= square) {
+                    return None;
+                }
+                previous = Some(square);
+
+                let square = Square::from_index(square as u8)?;
+                if board.piece_at(square).is_some() {
+                    return None;
+                }
+                board.set(piece, color, square);
+            }
+        }
+
+        is_legal(&board).then_some(board)
+    }
+}
+
+// pawns can't stand on the last rank, or on their first outside horde, and
+// the side that just moved can't have left its king in check
+fn is_legal(board: &Board) -> bool {
+    let white_pawns = board.pawns & board.white;
+    let black_pawns = board.pawns & board.black;
+
+    let mut white_ranks = Rank::R8.bitboard();
+    if board.variant != Variant::Horde {
+        white_ranks |= Rank::R1.bitboard();
+    }
+
+    white_pawns & white_ranks == 0
+        && black_pawns & (Rank::R1.bitboard() | Rank::R8.bitboard()) == 0
+        && board.king_safe(!board.side_to_move())
+}
+
+fn mirrored(board: &Board) -> Board {
+    let mut mirrored = *board;
+    for square in Square::all() {
+        mirrored.unset(square);
+    }
+    for square in Square::all() {
+        if let Some((piece, color)) = board.piece_at(square) {
+            mirrored.set(
+                piece,
+                color,
+                Square::from_index(square.index() ^ 7).unwrap(),
+            );
+        }
+    }
+
+    mirrored
+}
+
+/// Distance-to-mate tables for one variant, generated here or read from
+/// files written by [`Tablebase::write`]. Castling rights and en passant
+/// are left out: a position is probed as though it had neither.
+pub struct Tablebase {
+    variant: Variant,
+    tables: HashMap<Material, Vec<u16>>,
+}
+
+impl Tablebase {
+    /// An empty tablebase. Three-check and crazyhouse positions hold more
+    /// than the pieces on the board, so they can't have one.
+    pub fn new(variant: Variant) -> Result<Tablebase, String> {
+        if matches!(variant, Variant::ThreeCheck | Variant::Crazyhouse) {
+            return Err(format!("there are no tablebases for {variant}"));
+        }
+
+        Ok(Tablebase {
+            variant,
+            tables: HashMap::new(),
+        })
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// The material of every table there is.
+    pub fn materials(&self) -> impl Iterator<Item = &Material> {
+        self.tables.keys()
+    }
+
+    /// Generates the table for `material`, and first every table a capture
+    /// or promotion from it leads to. Four pieces take around a hundred
+    /// megabytes while generating.
+    pub fn generate(&mut self, material: &Material) -> Result<(), String> {
+        if self.tables.contains_key(material) {
+            return Ok(());
+        }
+
+        let layout = Layout::new(material);
+        if layout.pieces == 0 || layout.pieces > MAX_PIECES {
+            return Err(format!(
+                "{material} has {} pieces, tables go up to {MAX_PIECES}",
+                layout.pieces
+            ));
+        }
+
+        let size = layout.size();
+        let mut values = vec![INVALID; size];
+        let mut remaining = vec![0u8; size];
+        // one ply more than the longest win for the other side found so far
+        let mut longest = vec![0u16; size];
+        let mut levels: Vec<Vec<u32>> = Vec::new();
+
+        let push = |levels: &mut Vec<Vec<u32>>, plies: u16, index: usize| {
+            let plies = plies as usize;
+            if levels.len() <= plies {
+                levels.resize_with(plies + 1, Vec::new);
+            }
+            levels[plies].push(index as u32);
+        };
+
+        for index in 0..size {
+            let Some(board) = layout.board(index, self.variant) else {
+                continue;
+            };
+            values[index] = DRAW;
+
+            // the game is already over
+            let moves = board.legal_moves();
+            let outcome = board
+                .variant_outcome()
+                .or_else(|| moves.is_empty().then(|| board.outcome_without_moves()));
+            if let Some(outcome) = outcome {
+                let value = Dtm::of_outcome(outcome, board.side_to_move());
+                values[index] = value.encode();
+                remaining[index] = BLOCKED;
+                if value != Dtm::Draw {
+                    push(&mut levels, 0, index);
+                }
+                continue;
+            }
+
+            let mut inside = 0;
+            let mut blocked = false;
+            let mut fastest_win: Option<u16> = None;
+            for mv in moves {
+                let Some(mut child) = board.play(mv) else {
+                    continue;
+                };
+                child.en_passant_targets = 0;
+
+                if Material::of(&child) == *material {
+                    inside += 1;
+                    continue;
+                }
+
+                match self.child_value(&child)? {
+                    Dtm::Loss(plies) => {
+                        blocked = true;
+                        fastest_win =
+                            Some(fastest_win.map_or(plies + 1, |fastest| fastest.min(plies + 1)));
+                    }
+                    Dtm::Draw => blocked = true,
+                    Dtm::Win(plies) => longest[index] = longest[index].max(plies + 1),
+                }
+            }
+
+            if let Some(plies) = fastest_win {
+                // decided when its level comes up, unless something faster
+                // turns up inside the table first
+                push(&mut levels, plies, index);
+            }
+
+            if blocked {
+                remaining[index] = BLOCKED;
+            } else if inside == 0 {
+                values[index] = Dtm::Loss(longest[index]).encode();
+                push(&mut levels, longest[index], index);
+            } else {
+                remaining[index] = inside;
+            }
+        }
+
+        let mut plies = 0;
+        while plies < levels.len() {
+            let level = std::mem::take(&mut levels[plies]);
+            let plies_u16 = plies as u16;
+
+            for index in level {
+                let index = index as usize;
+                if remaining[index] == DONE {
+                    continue;
+                }
+
+                let value = match Dtm::decode(values[index]) {
+                    // a capture that wins, found before any move inside
+                    Some(Dtm::Draw) => {
+                        values[index] = Dtm::Win(plies_u16).encode();
+                        Dtm::Win(plies_u16)
+                    }
+                    Some(Dtm::Win(p)) | Some(Dtm::Loss(p)) if p != plies_u16 => continue,
+                    Some(value) => value,
+                    None => continue,
+                };
+                remaining[index] = DONE;
+
+                let Some(board) = layout.board(index, self.variant) else {
+                    continue;
+                };
+                for parent in predecessors(&layout, &board) {
+                    if values[parent] != DRAW {
+                        continue;
+                    }
+
+                    match value {
+                        Dtm::Loss(_) => {
+                            values[parent] = Dtm::Win(plies_u16 + 1).encode();
+                            push(&mut levels, plies_u16 + 1, parent);
+                        }
+                        Dtm::Win(_) if remaining[parent] < DONE => {
+                            remaining[parent] -= 1;
+                            longest[parent] = longest[parent].max(plies_u16 + 1);
+                            if remaining[parent] == 0 {
+                                values[parent] = Dtm::Loss(longest[parent]).encode();
+                                push(&mut levels, longest[parent], parent);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            plies += 1;
+        }
+
+        self.tables.insert(*material, values);
+        Ok(())
+    }
+
+    // the value of a position a capture or promotion leads to
+    fn child_value(&mut self, child: &Board) -> Result<Dtm, String> {
+        if let Some(outcome) = child.variant_outcome() {
+            return Ok(Dtm::of_outcome(outcome, child.side_to_move()));
+        }
+
+        let material = Material::of(child);
+        self.generate(&material)?;
+        self.probe(child)
+            .ok_or(format!("{} has no entry in {material}", child.fen()))
+    }
+
+    /// The value of `board` with best play, if there is a table for it.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.variant != self.variant {
+            return None;
+        }
+
+        let table = self.tables.get(&Material::of(board))?;
+        let layout = Layout::new(&Material::of(board));
+        let mut board = *board;
+        board.en_passant_targets = 0;
+        board.castling = CastlingRights::NONE;
+
+        Dtm::decode(table[layout.index(&board)])
+    }
+
+    /// A move that keeps to the value of `board`: the fastest win, the
+    /// slowest loss, or one that holds the draw.
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        let value = self.probe(board)?;
+
+        for mv in board.legal_moves() {
+            let Some(child) = board.play(mv) else {
+                continue;
+            };
+            // a move into a table that isn't loaded can't be judged
+            let reply = match child.variant_outcome() {
+                Some(outcome) => Dtm::of_outcome(outcome, child.side_to_move()),
+                None => match self.probe(&child) {
+                    Some(reply) => reply,
+                    None => continue,
+                },
+            };
+
+            let keeps = match (value, reply) {
+                (Dtm::Win(plies), Dtm::Loss(p)) | (Dtm::Loss(plies), Dtm::Win(p)) => p + 1 == plies,
+                (Dtm::Draw, Dtm::Draw) => true,
+                _ => false,
+            };
+            if keeps {
+                return Some(mv);
+            }
+        }
+
+        None
+    }
+
+    /// Writes every table into `dir`, one `.dtm` file each, named after its
+    /// material.
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        for (material, values) in &self.tables {
+            let mut bytes = MAGIC.to_vec();
+            for name in [self.variant.name(), &material.to_string()] {
+                bytes.push(name.len() as u8);
+                bytes.extend_from_slice(name.as_bytes());
+            }
+            write_varint(&mut bytes, values.len() as u64);
+
+            // runs of one value: the length, then the value
+            let mut i = 0;
+            while i < values.len() {
+                let run = values[i..].iter().take_while(|v| **v == values[i]).count();
+                write_varint(&mut bytes, run as u64);
+                write_varint(&mut bytes, values[i] as u64);
+                i += run;
+            }
+
+            let path = dir.join(format!("{material}.dtm"));
+            fs::write(&path, bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads every table for `variant` in `dir`.
+    pub fn open(dir: &Path, variant: Variant) -> Result<Tablebase, String> {
+        let mut tablebase = Tablebase::new(variant)?;
+
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|extension| extension == "dtm") {
+                let bytes = fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+                if let Some((material, values)) =
+                    read_table(&bytes, variant).map_err(|e| format!("{}: {e}", path.display()))?
+                {
+                    tablebase.tables.insert(material, values);
+                }
+            }
+        }
+
+        Ok(tablebase)
+    }
+}
+
+// every position that can move into `board` without leaving its table,
+// once for each move that does. the pieces are stepped back by hand and
+// the move played forward again to make sure it is legal
+fn predecessors(layout: &Layout, board: &Board) -> Vec<usize> {
+    let mut parents = Vec::new();
+
+    let mut boards = vec![*board];
+    let mirror = mirrored(board);
+    if layout.mirror && mirror != *board {
+        boards.push(mirror);
+    }
+
+    for board in boards {
+        let color = !board.side_to_move();
+        let occupied = board.white | board.black;
+        let forward = if color == Color::White { 1 } else { -1 };
+
+        for to in Bitboard(board.side(color)) {
+            let Some((piece, _)) = board.piece_at(to) else {
+                continue;
+            };
+
+            let i = to.index() as usize;
+            let from = match piece {
+                Piece::Pawn => {
+                    let mut from = 0;
+                    if let Some(one) = to.offset(0, -forward).filter(|s| occupied & s.bit() == 0) {
+                        from |= one.bit();
+                        if let Some(two) = one.offset(0, -forward) {
+                            if occupied & two.bit() == 0
+                                && board.double_push_ranks(color) & two.bit() != 0
+                            {
+                                from |= two.bit();
+                            }
+                        }
+                    }
+                    from
+                }
+                Piece::Knight => KNIGHT_ATTACKS[i] & !occupied,
+                Piece::Bishop => bishop_attacks(to, occupied) & !occupied,
+                Piece::Rook => rook_attacks(to, occupied) & !occupied,
+                Piece::Queen => queen_attacks(to, occupied) & !occupied,
+                Piece::King => KING_ATTACKS[i] & !occupied,
+            };
+
+            for from in Bitboard(from) {
+                let mut parent = board;
+                parent.unset(to);
+                parent.set(piece, color, from);
+                parent.whites_turn = !board.whites_turn;
+
+                if !layout.is_canonical(&parent) || !is_legal(&parent) {
+                    continue;
+                }
+
+                let Some(mut after) = parent.play(Move::new(from, to, None)) else {
+                    continue;
+                };
+                after.en_passant_targets = 0;
+                after.halfmove_clock = board.halfmove_clock;
+                after.fullmove_number = board.fullmove_number;
+
+                if after == board {
+                    parents.push(layout.index(&parent));
+                }
+            }
+        }
+    }
+
+    parents
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+fn read_varint(bytes: &[u8], at: &mut usize) -> Result<u64, String> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*at).ok_or("the file ends too soon")?;
+        *at += 1;
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return Ok(n);
+        }
+    }
+
+    Err("a number runs on too long".to_string())
+}
+
+// a table from a file, or `None` if it is for another variant
+fn read_table(bytes: &[u8], variant: Variant) -> Result<Option<(Material, Vec<u16>)>, String> {
+    if !bytes.starts_with(&MAGIC) {
+        return Err("not a dtm table".to_string());
+    }
+
+    let mut at = MAGIC.len();
+    let mut names = Vec::new();
+    for _ in 0..2 {
+        let len = *bytes.get(at).ok_or("the file ends too soon")? as usize;
+        let name = bytes
+            .get(at + 1..at + 1 + len)
+            .ok_or("the file ends too soon")?;
+        names.push(String::from_utf8_lossy(name).into_owned());
+        at += 1 + len;
+    }
+
+    if names[0].parse::<Variant>()? != variant {
+        return Ok(None);
+    }
+    let material: Material = names[1].parse()?;
+
+    let size = read_varint(bytes, &mut at)? as usize;
+    if size != Layout::new(&material).size() {
+        return Err(format!(
+            "{material} should have {} entries, not {size}",
+            Layout::new(&material).size()
+        ));
+    }
+
+    let mut values = Vec::with_capacity(size);
+    while values.len() < size {
+        let run = read_varint(bytes, &mut at)? as usize;
+        let value = u16::try_from(read_varint(bytes, &mut at)?).map_err(|e| e.to_string())?;
+        if values.len() + run > size {
+            return Err("a run goes past the end of the table".to_string());
+        }
+        values.resize(values.len() + run, value);
+    }
+
+    Ok(Some((material, values)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::kpk;
+    use crate::tablebase::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chessboard-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn variant_board(variant: Variant, fen: &str) -> Board {
+        Board::from_variant_fen(variant, fen).unwrap()
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let mut tablebase = Tablebase::new(Variant::KingOfTheHill).unwrap();
+        tablebase.generate(&"KvK".parse().unwrap()).unwrap();
+
+        let board = variant_board(Variant::KingOfTheHill, "k7/8/8/8/8/2K5/8/8 w - - 0 1");
+        assert_eq!(tablebase.probe(&board), Some(Dtm::Win(1)));
+        assert_eq!(
+            tablebase.best_move(&board),
+            Some(Move::new(Square::C3, Square::D4, None))
+        );
+
+        // the mirror image is the same position
+        let board = variant_board(Variant::KingOfTheHill, "7k/8/8/8/8/5K2/8/8 w - - 0 1");
+        assert_eq!(tablebase.probe(&board), Some(Dtm::Win(1)));
+
+        // the file round trip keeps every entry
+        let dir = test_dir("dtm");
+        tablebase.write(&dir).unwrap();
+        let read = Tablebase::open(&dir, Variant::KingOfTheHill).unwrap();
+        assert_eq!(read.tables, tablebase.tables);
+        assert!(Tablebase::open(&dir, Variant::Standard)
+            .unwrap()
+            .materials()
+            .next()
+            .is_none());
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(Tablebase::new(Variant::Crazyhouse).is_err());
+    }
+
+    #[test]
+    fn test_kpk() {
+        let mut tablebase = Tablebase::new(Variant::Standard).unwrap();
+        let material = "KPvK".parse().unwrap();
+        tablebase.generate(&material).unwrap();
+
+        // every promotion leads into a table of its own
+        for name in ["KQvK", "KRvK", "KBvK", "KNvK"] {
+            assert!(tablebase.tables.contains_key(&name.parse().unwrap()));
+        }
+
+        // and the wins are the ones the bitbase knows
+        let layout = Layout::new(&material);
+        for index in 0..layout.size() {
+            let Some(board) = layout.board(index, Variant::Standard) else {
+                continue;
+            };
+            let square = |piece, color| {
+                Bitboard(board.pieces(piece) & board.side(color))
+                    .iter()
+                    .next()
+                    .unwrap()
+            };
+            let wins = kpk::probe(
+                square(Piece::King, Color::White),
+                square(Piece::Pawn, Color::White),
+                square(Piece::King, Color::Black),
+                board.whites_turn,
+            );
+            let white_wins = matches!(
+                (board.whites_turn, tablebase.probe(&board)),
+                (true, Some(Dtm::Win(_))) | (false, Some(Dtm::Loss(_)))
+            );
+            assert_eq!(wins, white_wins, "{}", board.fen());
+        }
+
+        // without the tables a promotion leads into, the king moves that
+        // hold the draw are still found
+        for name in ["KQvK", "KRvK", "KBvK", "KNvK"] {
+            tablebase.tables.remove(&name.parse().unwrap());
+        }
+        let board = Board::from("6K1/1kP5/8/8/8/8/8/8 w - - 0 1");
+        assert_eq!(tablebase.probe(&board), Some(Dtm::Draw));
+        let mv = tablebase.best_move(&board).unwrap();
+        assert_eq!(mv.from, Square::G8);
+    }
+
+    #[test]
+    fn test_horde() {
+        let mut tablebase = Tablebase::new(Variant::Horde).unwrap();
+        let material = "PvK".parse().unwrap();
+        tablebase.generate(&material).unwrap();
+
+        // a lone piece can't mate, so white never wins
+        for (material, values) in &tablebase.tables {
+            for (index, value) in values.iter().enumerate() {
+                let white_wins = matches!(
+                    (index & 1, Dtm::decode(*value)),
+                    (0, Some(Dtm::Win(_))) | (1, Some(Dtm::Loss(_)))
+                );
+                assert!(!white_wins, "{material} at {index}");
+            }
+        }
+
+        // taking the last white piece wins
+        let board = variant_board(Variant::Horde, "8/8/8/8/8/8/1k6/P7 b - - 0 1");
+        assert_eq!(tablebase.probe(&board), Some(Dtm::Win(1)));
+        assert_eq!(
+            tablebase.best_move(&board),
+            Some(Move::new(Square::B2, Square::A1, None))
+        );
+
+        // out of reach, the pawn queens and the queen can't be caught
+        let board = variant_board(Variant::Horde, "8/P7/8/8/8/8/8/7k w - - 0 1");
+        assert_eq!(tablebase.probe(&board), Some(Dtm::Draw));
+    }
+
+    #[test]
+    fn test_kqk() {
+        let mut tablebase = Tablebase::new(Variant::Standard).unwrap();
+        let material = "KQvK".parse().unwrap();
+        tablebase.generate(&material).unwrap();
+
+        // the longest win takes ten moves
+        let longest = tablebase.tables[&material]
+            .iter()
+            .filter_map(|value| match Dtm::decode(*value) {
+                Some(Dtm::Win(plies)) => Some(plies),
+                _ => None,
+            })
+            .max();
+        assert_eq!(longest, Some(19));
+
+        assert_eq!(
+            tablebase.probe(&Board::from("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1")),
+            Some(Dtm::Win(1))
+        );
+        assert_eq!(
+            tablebase.probe(&Board::from("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")),
+            Some(Dtm::Draw)
+        );
+        // the king takes the queen, into the table for two kings
+        assert_eq!(
+            tablebase.probe(&Board::from("8/8/8/8/8/8/1k6/1Q5K b - - 0 1")),
+            Some(Dtm::Draw)
+        );
+
+        // following the best moves mates in as many plies as promised
+        let mut board = Board::from("8/8/8/3k4/8/8/8/Q6K w - - 0 1");
+        let Some(Dtm::Win(plies)) = tablebase.probe(&board) else {
+            panic!("KQvK is a win");
+        };
+        for _ in 0..plies {
+            board = board.play(tablebase.best_move(&board).unwrap()).unwrap();
+        }
+        assert!(board.in_check() && board.legal_moves().is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+// symmetries of the board, for augmenting training data and for checking that
+// evaluation and move generation treat both colours and both wings alike.
+//
+// castling rights follow their rooks and the en passant target follows the
+// board. whatever stops making sense is dropped: a rook that has left its
+// home rank cannot castle, and an en passant target is only kept if the side
+// to move could still take on it.
+
+use crate::castling::home_rank;
+use crate::{
+    bitboard_flip_horizontal, bitboard_flip_vertical, bitboard_rotate_180, Bitboard, Board,
+    CastlingRights, Color, Square,
+};
+
+impl Board {
+    /// Mirrors the ranks, keeping the colours and the side to move. White
+    /// pawns end up heading for rank 1, so this is mostly of use for pawnless
+    /// positions.
+    pub fn flip_vertical(&self) -> Board {
+        self.transformed(bitboard_flip_vertical, false)
+    }
+
+    /// Mirrors the files. The king ends up on the d-file, so a position that
+    /// keeps its castling rights becomes a chess960 one.
+    pub fn flip_horizontal(&self) -> Board {
+        self.transformed(bitboard_flip_horizontal, false)
+    }
+
+    /// Turns the board around, keeping the colours and the side to move.
+    pub fn rotate_180(&self) -> Board {
+        self.transformed(bitboard_rotate_180, false)
+    }
+
+    /// The same position with the colours swapped: the ranks are mirrored,
+    /// white becomes black and the other side is to move, along with its
+    /// castling rights, checks and pocket.
+    pub fn swap_colors(&self) -> Board {
+        self.transformed(bitboard_flip_vertical, true)
+    }
+
+    fn transformed(&self, flip: fn(u64) -> u64, swap: bool) -> Board {
+        let map = |square: Square| Square::from_index(flip(square.bit()).trailing_zeros() as u8);
+        let recolor = |color: Color| if swap { !color } else { color };
+
+        let mut board = *self;
+        board.white = flip(self.white);
+        board.black = flip(self.black);
+        if swap {
+            std::mem::swap(&mut board.white, &mut board.black);
+            board.whites_turn = !self.whites_turn;
+            board.checks.swap(0, 1);
+            board.pockets.swap(0, 1);
+        }
+        board.pawns = flip(self.pawns);
+        board.knights = flip(self.knights);
+        board.bishops = flip(self.bishops);
+        board.rooks = flip(self.rooks);
+        board.queens = flip(self.queens);
+        board.kings = flip(self.kings);
+        board.promoted = flip(self.promoted);
+
+        board.mailbox = [None; 64];
+        for square in Square::all() {
+            if let (Some((piece, color)), Some(to)) = (self.piece_at(square), map(square)) {
+                board.mailbox[to.index() as usize] = Some((piece, recolor(color)));
+            }
+        }
+        board.hash = board.placement_hash();
+
+        // mirroring the files turns kingside into queenside, and moves the
+        // king off the e-file
+        let mirrors_files = map(Square::A1).is_some_and(|a1| a1.file() != Square::A1.file());
+        board.chess960 = self.chess960 || mirrors_files;
+        board.castling = CastlingRights::NONE;
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let Some(file) = self.castling.rook(color, kingside) else {
+                    continue;
+                };
+                let Some(rook) = map(Square::new(file, home_rank(color))) else {
+                    continue;
+                };
+
+                let (color, kingside) = (recolor(color), kingside != mirrors_files);
+                if rook.rank() == home_rank(color) {
+                    board.castling.set(color, kingside, Some(rook.file()));
+                    if !board.valid_castling(color, kingside) {
+                        board.castling.set(color, kingside, None);
+                    }
+                }
+            }
+        }
+        if mirrors_files {
+            board.chess960 = board.needs_chess960();
+        }
+
+        board.en_passant_targets = flip(self.en_passant_targets);
+        let target = Bitboard(board.en_passant_targets).iter().next();
+        if !target.is_some_and(|target| board.valid_en_passant(target)) {
+            board.en_passant_targets = 0;
+        }
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_swap_colors() {
+        let board = Board::from("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+        let swapped = board.swap_colors();
+        assert_eq!(swapped.fen(), "r3k2r/8/8/8/3Pp3/8/8/R3K2R b KQkq d3 0 1");
+        assert!(swapped.swap_colors() == board);
+        assert_eq!(swapped.perft(2), board.perft(2));
+
+        let board = Board::from_variant_fen(
+            Variant::ThreeCheck,
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +2+0",
+        )
+        .unwrap();
+        assert_eq!(board.swap_colors().checks, [0, 2]);
+    }
+
+    #[test]
+    fn test_flips() {
+        let board = Board::from("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+
+        // the rooks leave their home ranks and the target ends up behind the
+        // pawn that would take on it
+        assert_eq!(
+            board.flip_vertical().fen(),
+            "R3K2R/8/8/8/3pP3/8/8/r3k2r w - - 0 1"
+        );
+        let flipped = board.flip_horizontal();
+        assert_eq!(flipped.fen(), "r2k3r/8/8/3Pp3/8/8/8/R2K3R w KQkq e6 0 1");
+        assert!(flipped.chess960);
+        assert_eq!(flipped.castling.rook(Color::White, true), Some(File::H));
+        assert_eq!(flipped.castling.rook(Color::Black, false), Some(File::A));
+        assert!(flipped.validate().is_ok());
+        assert!(flipped.flip_horizontal() == board);
+        assert!(board.flip_vertical().flip_horizontal() == board.rotate_180());
+        assert!(!board.rotate_180().chess960);
+        assert_eq!(
+            board.rotate_180().rotate_180().fen(),
+            "r3k2r/8/8/3pP3/8/8/8/R3K2R w - - 0 1"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// making a move on the board itself and taking it back again, for walking a
+// tree with one board instead of a copy per node. the move records each
+// square it changes as it goes, with what was there before, along with the
+// state it can't work out backwards; unmaking puts the squares back in the
+// reverse order. an illegal move is unmade before it is reported.
+
+use crate::{Board, CastlingRights, Color, Move, Piece, Pocket, Square};
+
+// more than any move changes: an atomic capture takes the piece off its
+// square, the captured one (twice, en passant) and up to nine in the blast,
+// and puts the capturing piece down
+const MAX_CHANGES: usize = 16;
+
+/// What [`Board::make`] needs to take a move back with [`Board::unmake`].
+#[derive(Clone, Copy)]
+pub struct Undo {
+    // the squares the move changed, in order, with what was on them before
+    changes: [(Square, Option<(Piece, Color)>); MAX_CHANGES],
+    count: usize,
+
+    en_passant_targets: u64,
+    castling: CastlingRights,
+    whites_turn: bool,
+    checks: [u8; 2],
+    pockets: [Pocket; 2],
+    promoted: u64,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+impl Undo {
+    // the state of `board` before a move
+    pub(crate) fn new(board: &Board) -> Undo {
+        Undo {
+            changes: [(Square::A1, None); MAX_CHANGES],
+            count: 0,
+            en_passant_targets: board.en_passant_targets,
+            castling: board.castling,
+            whites_turn: board.whites_turn,
+            checks: board.checks,
+            pockets: board.pockets,
+            promoted: board.promoted,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+        }
+    }
+}
+
+impl Board {
+    /// Plays `mv` on this board, or returns `None` and leaves it as it was
+    /// if the move is illegal.
+    pub fn make(&mut self, mv: Move) -> Option<Undo> {
+        let mut undo = Undo::new(self);
+        match mv.drop {
+            Some(piece) => self.make_drop(piece, mv.to, &mut undo),
+            None => self.make_move(mv.from, mv.to, mv.promote, &mut undo),
+        }
+        .ok()?;
+
+        Some(undo)
+    }
+
+    /// Takes back the move [`make`](Board::make) returned `undo` for. It has
+    /// to be the last move made.
+    pub fn unmake(&mut self, undo: &Undo) {
+        for &(square, piece) in undo.changes[..undo.count].iter().rev() {
+            match piece {
+                Some((piece, color)) => self.set(piece, color, square),
+                None => self.unset(square),
+            }
+        }
+
+        self.en_passant_targets = undo.en_passant_targets;
+        self.castling = undo.castling;
+        self.whites_turn = undo.whites_turn;
+        self.checks = undo.checks;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+    }
+
+    // `set` and `unset` for moves, remembering what was on the square
+    pub(crate) fn set_undoable(
+        &mut self,
+        undo: &mut Undo,
+        piece: Piece,
+        color: Color,
+        square: Square,
+    ) {
+        undo.changes[undo.count] = (square, self.piece_at(square));
+        undo.count += 1;
+        self.set(piece, color, square);
+    }
+
+    pub(crate) fn unset_undoable(&mut self, undo: &mut Undo, square: Square) {
+        if let Some(piece) = self.piece_at(square) {
+            undo.changes[undo.count] = (square, Some(piece));
+            undo.count += 1;
+            self.unset(square);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// checks that a position could have come from a legal game, at least as far as
+// can be told without its history. antichess has no check and no castling, and
+// its kings are ordinary pieces that can be taken or promoted to. in horde white
+// has no king and may have pawns on its first rank, and in racing kings
+// neither king may ever be in check
+
+use std::fmt;
+
+use crate::castling::home_rank;
+use crate::{Bitboard, Board, Color, File, Piece, Rank, Square, Variant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    /// `color` has this many kings instead of exactly one (none for white
+    /// in horde).
+    KingCount(Color, u32),
+    PawnOnBackRank(Square),
+    /// The side that just moved left its own king in check.
+    OpponentInCheck,
+    /// The side to move is in check, which racing kings never allows.
+    InCheck,
+    /// `color` may still castle on this side, but its king or rook has left
+    /// its starting square.
+    CastlingRights {
+        color: Color,
+        kingside: bool,
+    },
+    /// No double pawn push could have left this en passant target behind.
+    EnPassant(Square),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::KingCount(color, count) => {
+                write!(f, "{color:?} has the wrong number of kings: {count}")
+            }
+            PositionError::PawnOnBackRank(square) => write!(f, "there is a pawn on {square}"),
+            PositionError::OpponentInCheck => {
+                write!(f, "the side that is not to move is in check")
+            }
+            PositionError::InCheck => write!(f, "the side to move is in check"),
+            PositionError::CastlingRights { color, kingside } => write!(
+                f,
+                "{color:?} can castle {}, but the king or rook has moved",
+                if *kingside { "kingside" } else { "queenside" }
+            ),
+            PositionError::EnPassant(square) => {
+                write!(f, "{square} cannot be an en passant target")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Board {
+    /// Every reason this position can't occur in a game, if there are any.
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        let mut errors = Vec::new();
+        let antichess = self.variant == Variant::Antichess;
+
+        let horde = self.variant == Variant::Horde;
+
+        for color in [Color::White, Color::Black] {
+            let kings = (self.kings & self.side(color)).count_ones();
+            let needed = if horde && color == Color::White { 0 } else { 1 };
+            if kings != needed && !antichess {
+                errors.push(PositionError::KingCount(color, kings));
+            }
+        }
+
+        let mut back_ranks = self.pawns & (Rank::R1.bitboard() | Rank::R8.bitboard());
+        if horde {
+            back_ranks &= !(self.side(Color::White) & Rank::R1.bitboard());
+        }
+        for square in Bitboard(back_ranks) {
+            errors.push(PositionError::PawnOnBackRank(square));
+        }
+
+        if self.king_in_check(!self.side_to_move()) {
+            errors.push(PositionError::OpponentInCheck);
+        }
+        if self.variant == Variant::RacingKings && self.in_check() {
+            errors.push(PositionError::InCheck);
+        }
+
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                if self.castling.rook(color, kingside).is_some()
+                    && (antichess || !self.valid_castling(color, kingside))
+                {
+                    errors.push(PositionError::CastlingRights { color, kingside });
+                }
+            }
+        }
+
+        for square in Bitboard(self.en_passant_targets) {
+            if !self.valid_en_passant(square) {
+                errors.push(PositionError::EnPassant(square));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // the rook has to be on the right side of the king, and in standard chess
+    // both have to be on their usual files
+    pub(crate) fn valid_castling(&self, color: Color, kingside: bool) -> bool {
+        let Some(file) = self.castling.rook(color, kingside) else {
+            return false;
+        };
+
+        let rank = home_rank(color);
+        let king = Bitboard(self.kings & self.side(color) & rank.bitboard())
+            .iter()
+            .next();
+        king.is_some_and(|king| {
+            self.piece_at(Square::new(file, rank)) == Some((Piece::Rook, color))
+                && (file > king.file()) == kingside
+                && (self.chess960 || king.file() == File::E)
+        })
+    }
+
+    // the pawn that just moved two squares must be in front of the target,
+    // and both the target and the square it came from must be empty
+    pub(crate) fn valid_en_passant(&self, target: Square) -> bool {
+        let (mover, rank, forward) = if self.whites_turn {
+            (Color::Black, Rank::R6, -1)
+        } else {
+            (Color::White, Rank::R3, 1)
+        };
+
+        if self.en_passant_targets.count_ones() != 1 || target.rank() != rank {
+            return false;
+        }
+
+        let (Some(pawn), Some(origin)) = (target.offset(0, forward), target.offset(0, -forward))
+        else {
+            return false;
+        };
+
+        self.piece_at(pawn) == Some((Piece::Pawn, mover))
+            && self.piece_at(target).is_none()
+            && self.piece_at(origin).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::*;
+    use crate::CastlingRights;
+
+    fn errors(board: Board) -> Vec<PositionError> {
+        board.validate().err().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(Board::default().validate(), Ok(()));
+        assert_eq!(
+            Board::from("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_kings_and_pawns() {
+        let mut board = Board::from("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        board.set(Piece::King, Color::White, Square::A1);
+        board.set(Piece::Pawn, Color::Black, Square::H1);
+        assert_eq!(
+            errors(board),
+            vec![
+                PositionError::KingCount(Color::White, 2),
+                PositionError::PawnOnBackRank(Square::H1),
+            ]
+        );
+
+        board.unset(Square::E8);
+        assert!(errors(board).contains(&PositionError::KingCount(Color::Black, 0)));
+    }
+
+    #[test]
+    fn test_opponent_in_check() {
+        let board = Board::from("4k3/8/8/8/8/8/8/4RK2 w - - 0 1");
+        assert_eq!(errors(board), vec![PositionError::OpponentInCheck]);
+    }
+
+    #[test]
+    fn test_castling_rights() {
+        // FEN can't give a right without its rook, so set them directly
+        let mut board = Board::from("r3k3/8/8/8/8/8/8/4K2R w - - 0 1");
+        board.castling = CastlingRights::STANDARD;
+        assert_eq!(
+            errors(board),
+            vec![
+                PositionError::CastlingRights {
+                    color: Color::White,
+                    kingside: false
+                },
+                PositionError::CastlingRights {
+                    color: Color::Black,
+                    kingside: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_antichess() {
+        // any number of kings, and a king may be left attacked
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/8/8/8/8/8/r7/K3K3 b - - 0 1").unwrap();
+        assert_eq!(board.validate(), Ok(()));
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/8/8/8/8/8/p7/R7 w - - 0 1").unwrap();
+        assert_eq!(board.validate(), Ok(()));
+
+        // and no castling at all
+        let mut board = Board::start(Variant::Antichess);
+        board.castling = CastlingRights::STANDARD;
+        assert_eq!(errors(board).len(), 4);
+    }
+
+    #[test]
+    fn test_horde_and_racing_kings() {
+        assert_eq!(Board::start(Variant::Horde).validate(), Ok(()));
+        assert_eq!(Board::start(Variant::RacingKings).validate(), Ok(()));
+
+        // white has no king in horde, and only its own pawns may stand on
+        // the first rank
+        let mut board = Board::start(Variant::Horde);
+        board.set(Piece::King, Color::White, Square::E4);
+        board.set(Piece::Pawn, Color::Black, Square::H1);
+        assert_eq!(
+            errors(board),
+            vec![
+                PositionError::KingCount(Color::White, 1),
+                PositionError::PawnOnBackRank(Square::H1),
+            ]
+        );
+
+        // nobody is ever in check in racing kings
+        let board =
+            Board::from_variant_fen(Variant::RacingKings, "8/8/8/8/8/R7/k7/7K b - - 0 1").unwrap();
+        assert_eq!(errors(board), vec![PositionError::InCheck]);
+    }
+
+    #[test]
+    fn test_en_passant() {
+        // white to move, so the target has to be on the sixth rank
+        let mut board = Board::from("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1");
+        board.en_passant_targets = Square::D3.bit();
+        assert_eq!(errors(board), vec![PositionError::EnPassant(Square::D3)]);
+
+        // no pawn in front of the target
+        board.en_passant_targets = Square::E6.bit();
+        assert_eq!(errors(board), vec![PositionError::EnPassant(Square::E6)]);
+
+        board.en_passant_targets = Square::D6.bit();
+        assert_eq!(board.validate(), Ok(()));
+    }
+}
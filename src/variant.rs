@@ -0,0 +1,561 @@
+// chess variants played with the usual pieces on the usual board. each one
+// changes a few of the rules `move_piece` applies: which moves are legal, what
+// else happens when a move is made, and when the game is over
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::attacks::KING_ATTACKS;
+use crate::{piece_moves, Bitboard, Board, CastlingRights, Color, Piece, Rank, Square, Undo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// A king reaching one of the four centre squares wins.
+    KingOfTheHill,
+    /// Giving check for the third time wins.
+    ThreeCheck,
+    /// A capture blows up the capturing piece and every piece other than a
+    /// pawn next to the capture square. Blowing up the enemy king wins.
+    Atomic,
+    /// Captured pieces change sides and may be dropped back on the board.
+    Crazyhouse,
+    /// Captures are forced and there is no check: the king is just another
+    /// piece. Losing every piece, or having no move, wins.
+    Antichess,
+    /// White has 36 pawns and no king, and loses once they are all gone.
+    /// Pawns on the first rank may move two squares.
+    Horde,
+    /// Giving check is not allowed, and the first king to reach the eighth
+    /// rank wins. If black's king can get there right after white's, it's a
+    /// draw.
+    RacingKings,
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Color),
+    Draw,
+}
+
+/// d4, e4, d5 and e5, the hill in King of the Hill.
+pub const CENTER: u64 = 0x0000_0018_1800_0000;
+
+impl Variant {
+    pub const ALL: [Variant; 8] = [
+        Variant::Standard,
+        Variant::KingOfTheHill,
+        Variant::ThreeCheck,
+        Variant::Atomic,
+        Variant::Crazyhouse,
+        Variant::Antichess,
+        Variant::Horde,
+        Variant::RacingKings,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::ThreeCheck => "threecheck",
+            Variant::Atomic => "atomic",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Antichess => "antichess",
+            Variant::Horde => "horde",
+            Variant::RacingKings => "racingkings",
+        }
+    }
+
+    pub fn start_fen(self) -> &'static str {
+        match self {
+            Variant::ThreeCheck => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+            Variant::Crazyhouse => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            Variant::Horde => {
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+            }
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+            _ => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        match name.as_str() {
+            "standard" | "chess" | "chess960" => Ok(Variant::Standard),
+            "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+            "threecheck" | "3check" => Ok(Variant::ThreeCheck),
+            "atomic" => Ok(Variant::Atomic),
+            "crazyhouse" | "zh" => Ok(Variant::Crazyhouse),
+            "antichess" | "losingchess" | "giveaway" => Ok(Variant::Antichess),
+            "horde" => Ok(Variant::Horde),
+            "racingkings" => Ok(Variant::RacingKings),
+            _ => Err(format!("unknown variant: {s}")),
+        }
+    }
+}
+
+impl Board {
+    /// The starting position of `variant`.
+    pub fn start(variant: Variant) -> Board {
+        Board::from_variant_fen(variant, variant.start_fen()).unwrap()
+    }
+
+    pub fn from_variant_fen(variant: Variant, fen: &str) -> Result<Board, String> {
+        let mut board = Board::from_fen(fen)?;
+        board.variant = variant;
+        if variant == Variant::Antichess {
+            // antichess has no castling
+            board.castling = CastlingRights::NONE;
+            board.chess960 = false;
+        }
+        Ok(board)
+    }
+
+    /// How the game has ended, or `None` while it is still going.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.variant_outcome().or_else(|| {
+            self.legal_moves()
+                .is_empty()
+                .then(|| self.outcome_without_moves())
+        })
+    }
+
+    /// A result the variant's own rules have already decided, whatever moves
+    /// are left.
+    pub fn variant_outcome(&self) -> Option<Outcome> {
+        match self.variant {
+            Variant::Standard | Variant::Crazyhouse => None,
+            Variant::KingOfTheHill => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.kings & self.side(*color) & CENTER != 0)
+                .map(Outcome::Win),
+            Variant::ThreeCheck => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.checks[*color as usize] >= 3)
+                .map(Outcome::Win),
+            Variant::Atomic => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.kings & self.side(*color) == 0)
+                .map(|color| Outcome::Win(!color)),
+            Variant::Antichess => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| self.side(*color) == 0)
+                .map(Outcome::Win),
+            Variant::Horde => (self.side(Color::White) == 0).then_some(Outcome::Win(Color::Black)),
+            Variant::RacingKings => self.race_outcome(),
+        }
+    }
+
+    // a king on the eighth rank wins, unless the black king can follow it
+    // there straight away
+    fn race_outcome(&self) -> Option<Outcome> {
+        let goal = Rank::R8.bitboard();
+        let white = self.kings & self.white & goal != 0;
+        let black = self.kings & self.black & goal != 0;
+
+        match (white, black) {
+            (true, true) => Some(Outcome::Draw),
+            (false, true) => Some(Outcome::Win(Color::Black)),
+            (true, false) if self.whites_turn || !self.black_can_finish() => {
+                Some(Outcome::Win(Color::White))
+            }
+            _ => None,
+        }
+    }
+
+    // whether black has a legal king move onto the eighth rank. this can't go
+    // through `move_piece`, which asks for the outcome first
+    fn black_can_finish(&self) -> bool {
+        let Some(king) = self.king_square(Color::Black) else {
+            return false;
+        };
+
+        Bitboard(piece_moves(*self, king) & Rank::R8.bitboard())
+            .iter()
+            .any(|to| {
+                let mut test = *self;
+                test.unset(king);
+                test.set(Piece::King, Color::Black, to);
+                test.whites_turn = true;
+                test.king_safe(Color::Black)
+            })
+    }
+
+    // the ranks pawns of `color` may move two squares from
+    pub(crate) fn double_push_ranks(&self, color: Color) -> u64 {
+        match color {
+            Color::White if self.variant == Variant::Horde => {
+                Rank::R1.bitboard() | Rank::R2.bitboard()
+            }
+            Color::White => Rank::R2.bitboard(),
+            Color::Black => Rank::R7.bitboard(),
+        }
+    }
+
+    // the result when the side to move has no legal moves
+    pub(crate) fn outcome_without_moves(&self) -> Outcome {
+        if self.variant == Variant::Antichess {
+            Outcome::Win(self.side_to_move())
+        } else if self.in_check() {
+            Outcome::Win(!self.side_to_move())
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    // whether the king of `color` is attacked. in atomic chess a king next to
+    // the enemy king can't be: taking it would blow up the taker's own king
+    pub(crate) fn king_in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+        let Some(king) = self.king_square(color) else {
+            return false;
+        };
+
+        if self.variant == Variant::Atomic
+            && KING_ATTACKS[king.index() as usize] & self.kings & self.side(!color) != 0
+        {
+            return false;
+        }
+
+        self.attackers_to(king, !color) != 0
+    }
+
+    // whether `color` may leave the board like this after its own move. in
+    // racing kings that also means not giving check
+    pub(crate) fn king_safe(&self, color: Color) -> bool {
+        if self.variant == Variant::RacingKings && self.king_in_check(!color) {
+            return false;
+        }
+
+        if self.variant == Variant::Atomic {
+            if self.kings & self.side(color) == 0 {
+                return false;
+            }
+            if self.kings & self.side(!color) == 0 {
+                return true;
+            }
+        }
+
+        !self.king_in_check(color)
+    }
+
+    // whether the side to move can take anything, in which case antichess
+    // doesn't let it do anything else
+    pub(crate) fn can_capture(&self) -> bool {
+        let color = self.side_to_move();
+        let enemy = self.side(!color);
+
+        Bitboard(self.side(color)).iter().any(|from| {
+            let en_passant = match self.pawns & from.bit() {
+                0 => 0,
+                _ => self.en_passant_targets,
+            };
+            piece_moves(*self, from) & (enemy | en_passant) != 0
+        })
+    }
+
+    // the atomic explosion after a capture on `square`: the capturing piece
+    // and every piece but a pawn around it are removed, taking any castling
+    // rights they had with them
+    pub(crate) fn explode(&mut self, square: Square, undo: &mut Undo) {
+        let blast = square.bit() | (KING_ATTACKS[square.index() as usize] & !self.pawns);
+
+        for square in Bitboard(blast & (self.white | self.black)) {
+            if let Some((Piece::King, color)) = self.piece_at(square) {
+                self.castling.clear(color);
+            }
+
+            self.castling.touch(square);
+            self.unset_undoable(undo, square);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::variant::*;
+    use crate::Move;
+
+    fn play(board: Board, moves: &[&str]) -> Board {
+        moves.iter().fold(board, |board, uci| {
+            board.play(board.parse_uci(uci).unwrap()).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_names() {
+        for variant in Variant::ALL {
+            assert_eq!(variant.name().parse(), Ok(variant));
+            assert_eq!(Board::start(variant).variant, variant);
+        }
+        assert_eq!("King of the Hill".parse(), Ok(Variant::KingOfTheHill));
+        assert_eq!("3check".parse(), Ok(Variant::ThreeCheck));
+        assert!("bughouse".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let board =
+            Board::from_variant_fen(Variant::KingOfTheHill, "4k3/8/8/8/8/4K3/8/8 w - - 0 1")
+                .unwrap();
+        assert_eq!(board.outcome(), None);
+
+        let board = play(board, &["e3e4"]);
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::White)));
+        assert!(board.legal_moves().is_empty());
+
+        // the same move is nothing special in standard chess
+        let board = Board::from("4k3/8/8/8/8/4K3/8/8 w - - 0 1");
+        assert_eq!(play(board, &["e3e4"]).outcome(), None);
+    }
+
+    #[test]
+    fn test_three_check() {
+        let board = Board::start(Variant::ThreeCheck);
+        assert_eq!(board.fen(), Variant::ThreeCheck.start_fen());
+
+        let board = play(board, &["e2e4", "d7d6", "f1b5", "c7c6", "b5c6", "b8c6"]);
+        assert_eq!(board.checks, [2, 0]);
+        assert_eq!(
+            board.fen(),
+            "r1bqkbnr/pp2pppp/2np4/8/4P3/8/PPPP1PPP/RNBQK1NR w KQkq - 1+3 0 4"
+        );
+
+        // counters may also be given as checks so far, after the clocks
+        let board = Board::from("4k3/8/8/8/8/8/8/4K2R w K - 0 1 +2+1");
+        assert_eq!(board.variant, Variant::ThreeCheck);
+        assert_eq!(board.checks, [2, 1]);
+        assert_eq!(board.outcome(), None);
+
+        let board = play(board, &["h1h8"]);
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::White)));
+
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 4+0 0 1").is_err());
+    }
+
+    #[test]
+    fn test_atomic_explosion() {
+        // the rook takes on d7: it, the queen on d8 and the knight on c6 all
+        // go, but the pawns on c7 and e7 survive the blast
+        let board = Board::from_variant_fen(
+            Variant::Atomic,
+            "r2q1knr/pppbpppp/2n5/8/8/8/8/3RK3 w - - 0 1",
+        )
+        .unwrap();
+        let board = play(board, &["d1d7"]);
+        assert_eq!(board.fen(), "r4knr/ppp1pppp/8/8/8/8/8/4K3 b - - 0 1");
+
+        // a rook caught in a blast takes its castling right with it
+        let board =
+            Board::from_variant_fen(Variant::Atomic, "r3k2r/8/3b4/8/8/8/7P/R3K2R b KQkq - 0 1")
+                .unwrap();
+        let board = play(board, &["d6h2"]);
+        assert_eq!(board.fen(), "r3k2r/8/8/8/8/8/8/R3K3 w Qkq - 0 2");
+    }
+
+    #[test]
+    fn test_atomic_kings() {
+        // blowing up the king ends the game, even from "check"
+        let board =
+            Board::from_variant_fen(Variant::Atomic, "4k3/4n3/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let board = play(board, &["a1a7", "e8d8", "a7e7"]);
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::White)));
+
+        // kings can't capture, and can't blow themselves up
+        let board =
+            Board::from_variant_fen(Variant::Atomic, "4k3/8/8/8/8/8/3pq3/4K3 w - - 0 1").unwrap();
+        assert!(board
+            .play(Move::new(Square::E1, Square::E2, None))
+            .is_none());
+        assert!(board
+            .play(Move::new(Square::E1, Square::D2, None))
+            .is_none());
+
+        // touching kings can't give check, so the rook doesn't pin anything
+        let board =
+            Board::from_variant_fen(Variant::Atomic, "8/8/8/8/8/3k4/3K4/3r4 w - - 0 1").unwrap();
+        assert!(!board.in_check());
+        assert!(board
+            .play(Move::new(Square::D2, Square::C3, None))
+            .is_some());
+        assert!(board
+            .play(Move::new(Square::D2, Square::E1, None))
+            .is_none());
+    }
+
+    #[test]
+    fn test_antichess() {
+        // e4 d5 leaves white having to take
+        let board = play(Board::start(Variant::Antichess), &["e2e4", "d7d5"]);
+        assert_eq!(
+            board.legal_moves(),
+            vec![Move::new(Square::E4, Square::D5, None)]
+        );
+
+        // no check: the king may walk into the rook and be taken
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/8/8/8/8/8/r7/4K3 w - - 0 1").unwrap();
+        assert!(!board.in_check());
+        let board = play(board, &["e1d2", "a2d2"]);
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::White)));
+
+        // a pawn may become a king
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/P7/8/8/8/8/8/7r w - - 0 1").unwrap();
+        let king = board.parse_uci("a7a8k").unwrap();
+        assert!(board.legal_moves().contains(&king));
+        assert_eq!(
+            board.play(king).unwrap().piece_at(Square::A8),
+            Some((Piece::King, Color::White))
+        );
+        assert!(Board::from("k7/P7/8/8/8/8/8/7K w - - 0 1")
+            .play(king)
+            .is_none());
+        // and nothing promotes to a pawn
+        for variant in Variant::ALL {
+            let mut board = Board::from("8/P6k/8/8/8/8/8/K7 w - - 0 1");
+            board.variant = variant;
+            assert!(board
+                .move_piece(Square::A7, Square::A8, Some(Piece::Pawn))
+                .is_err());
+            let knight = board.move_piece(Square::A7, Square::A8, Some(Piece::Knight));
+            assert!(knight.is_ok(), "{variant}: {knight:?}");
+        }
+
+        // having no move wins too: black's pawn is blocked
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/8/8/8/8/p7/P7/8 b - - 0 1").unwrap();
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::Black)));
+
+        // castling rights in the FEN are dropped
+        let board = Board::from_variant_fen(
+            Variant::Antichess,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(board.castling, CastlingRights::NONE);
+        let king_moves: Vec<_> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == Square::E1)
+            .map(|mv| mv.to)
+            .collect();
+        assert_eq!(king_moves, vec![Square::D1, Square::F1]);
+    }
+
+    #[test]
+    fn test_horde() {
+        let board = Board::start(Variant::Horde);
+        assert_eq!(
+            (board.pieces(Piece::Pawn) & board.side(Color::White)).count_ones(),
+            36
+        );
+
+        // first rank pawns move two squares, but can't be taken en passant
+        let board =
+            Board::from_variant_fen(Variant::Horde, "4k3/8/8/8/8/1p6/8/P7 w - - 0 1").unwrap();
+        let board = play(board, &["a1a3"]);
+        assert_eq!(board.en_passant_targets, 0);
+
+        // white has no king to be checked, and loses with its last pawn
+        assert!(!board.in_check());
+        let board = play(
+            board,
+            &["b3b2", "a3a4", "b2b1q", "a4a5", "b1a1", "a5a6", "a1a6"],
+        );
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::Black)));
+    }
+
+    #[test]
+    fn test_racing_kings() {
+        // the rook can't give check, not even to get out of the way
+        let board =
+            Board::from_variant_fen(Variant::RacingKings, "8/8/8/8/8/2R5/k7/7K w - - 0 1").unwrap();
+        assert!(board
+            .play(Move::new(Square::C3, Square::A3, None))
+            .is_none());
+        assert!(board
+            .play(Move::new(Square::C3, Square::C2, None))
+            .is_none());
+        assert!(board
+            .play(Move::new(Square::C3, Square::C1, None))
+            .is_some());
+
+        // white got there first, but black can still follow
+        let board =
+            Board::from_variant_fen(Variant::RacingKings, "8/1K5k/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let board = play(board, &["b7b8"]);
+        assert_eq!(board.outcome(), None);
+        assert_eq!(play(board, &["h7h8"]).outcome(), Some(Outcome::Draw));
+        assert_eq!(
+            play(board, &["h7h6"]).outcome(),
+            Some(Outcome::Win(Color::White))
+        );
+
+        // unless it's too far behind
+        let board =
+            Board::from_variant_fen(Variant::RacingKings, "8/1K6/7k/8/8/8/8/8 w - - 0 1").unwrap();
+        let board = play(board, &["b7b8"]);
+        assert_eq!(board.outcome(), Some(Outcome::Win(Color::White)));
+    }
+
+    #[test]
+    fn test_perft() {
+        // 45 more than standard chess: the first explosions come at depth 4
+        assert_eq!(Board::start(Variant::Atomic).perft(4), 197326);
+        assert_eq!(Board::start(Variant::KingOfTheHill).perft(3), 8902);
+
+        let board = Board::from_variant_fen(
+            Variant::ThreeCheck,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1",
+        )
+        .unwrap();
+        assert_eq!(board.perft(3), 97848);
+
+        assert_eq!(Board::start(Variant::Antichess).perft(4), 153299);
+
+        // once the pawns meet every move is a forced capture, until one side
+        // has nothing left
+        let board =
+            Board::from_variant_fen(Variant::Antichess, "8/1p6/8/8/8/8/P7/8 w - - 0 1").unwrap();
+        let counts: Vec<_> = (1..=6).map(|depth| board.perft(depth)).collect();
+        assert_eq!(counts, [2, 4, 4, 3, 1, 0]);
+
+        assert_eq!(Board::start(Variant::Horde).perft(4), 23310);
+        let board = Board::from_variant_fen(
+            Variant::Horde,
+            "4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1",
+        )
+        .unwrap();
+        assert_eq!(board.perft(3), 6633);
+        // c1c3 leaves no en passant target for the pawn on b3
+        let board = Board::from_variant_fen(
+            Variant::Horde,
+            "k7/5p2/4p2P/3p2P1/2p2P2/1p2P2P/p2P2P1/2P2P2 w - - 0 1",
+        )
+        .unwrap();
+        assert_eq!(board.perft(4), 33781);
+
+        assert_eq!(Board::start(Variant::RacingKings).perft(3), 11264);
+    }
+}
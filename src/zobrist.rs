@@ -0,0 +1,226 @@
+// zobrist keys are generated at compile time from a fixed seed, so hashes are
+// stable between runs (and between threads sharing a transposition table)
+
+use crate::{Board, CastlingRights, Color, Piece, Pocket, Variant};
+
+const fn splitmix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (state, z ^ (z >> 31))
+}
+
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    // by colour, side and the rook's file
+    castling: [[[u64; 8]; 2]; 2],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+    checks: [[u64; 4]; 2],
+    // by colour, piece and how many are held
+    pockets: [[[u64; 16]; 5]; 2],
+    promoted: [u64; 64],
+}
+
+const fn generate() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        castling: [[[0; 8]; 2]; 2],
+        en_passant: [0; 8],
+        black_to_move: 0,
+        checks: [[0; 4]; 2],
+        pockets: [[[0; 16]; 5]; 2],
+        promoted: [0; 64],
+    };
+
+    let mut state = 0x6368657373626F61;
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut square = 0;
+            while square < 64 {
+                let (next, key) = splitmix(state);
+                state = next;
+                keys.pieces[color][piece][square] = key;
+                square += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    let mut i = 0;
+    while i < 2 * 2 * 8 {
+        let (next, key) = splitmix(state);
+        state = next;
+        keys.castling[i / 16][i / 8 % 2][i % 8] = key;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        let (next, key) = splitmix(state);
+        state = next;
+        keys.en_passant[i] = key;
+        i += 1;
+    }
+
+    let (next, key) = splitmix(state);
+    state = next;
+    keys.black_to_move = key;
+
+    let mut i = 0;
+    while i < 8 {
+        let (next, key) = splitmix(state);
+        state = next;
+        keys.checks[i / 4][i % 4] = key;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 2 * 5 * 16 {
+        let (next, key) = splitmix(state);
+        state = next;
+        keys.pockets[i / 80][i / 16 % 5][i % 16] = key;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 64 {
+        let (next, key) = splitmix(state);
+        state = next;
+        keys.promoted[i] = key;
+        i += 1;
+    }
+
+    keys
+}
+
+static KEYS: Keys = generate();
+
+#[inline]
+pub fn piece_key(piece: Piece, color: Color, square: u8) -> u64 {
+    KEYS.pieces[color as usize][piece as usize][square as usize]
+}
+
+// keyed on the rook's file, so chess960 rights on different rooks differ
+fn castling_key(castling: CastlingRights) -> u64 {
+    let mut hash = 0;
+    for color in [Color::White, Color::Black] {
+        for kingside in [true, false] {
+            if let Some(file) = castling.rook(color, kingside) {
+                hash ^= KEYS.castling[color as usize][kingside as usize][file.index() as usize];
+            }
+        }
+    }
+
+    hash
+}
+
+fn en_passant_key(mut ep: u64) -> u64 {
+    let mut hash = 0;
+    while ep != 0 {
+        hash ^= KEYS.en_passant[(ep.trailing_zeros() & 7) as usize];
+        ep &= ep - 1;
+    }
+
+    hash
+}
+
+impl Board {
+    /// A zobrist hash of the position: piece placement, side to move,
+    /// castling rights, en passant file, and the checks given in three-check
+    /// or the pockets and promoted pieces in crazyhouse. The placement is
+    /// hashed as pieces are set and taken off, so this costs a handful of
+    /// lookups rather than a pass over the board.
+    pub fn hash(&self) -> u64 {
+        // the rest are public fields, which can change without the board
+        // knowing, so their keys are added here
+        let mut hash = self.hash;
+        hash ^= castling_key(self.castling) ^ en_passant_key(self.en_passant_targets);
+        if !self.whites_turn {
+            hash ^= KEYS.black_to_move;
+        }
+
+        hash ^ self.variant_key()
+    }
+
+    // the keys of every piece on the board, the slow way
+    pub(crate) fn placement_hash(&self) -> u64 {
+        let mut hash = 0;
+        for color in [Color::White, Color::Black] {
+            for piece in Piece::ALL {
+                let mut bb = self.pieces(piece) & self.side(color);
+                while bb != 0 {
+                    hash ^= piece_key(piece, color, bb.trailing_zeros() as u8);
+                    bb &= bb - 1;
+                }
+            }
+        }
+
+        hash
+    }
+
+    // the checks in three-check, or the pockets and promoted pieces in
+    // crazyhouse
+    fn variant_key(&self) -> u64 {
+        let mut hash = 0;
+
+        if self.variant == Variant::ThreeCheck {
+            for (keys, checks) in KEYS.checks.iter().zip(self.checks) {
+                hash ^= keys[checks.min(3) as usize];
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            for color in [Color::White, Color::Black] {
+                for piece in Pocket::PIECES {
+                    let count = self.pocket(color).count(piece).min(15);
+                    hash ^= KEYS.pockets[color as usize][piece as usize][count as usize];
+                }
+            }
+
+            for square in self.promoted() {
+                hash ^= KEYS.promoted[square.index() as usize];
+            }
+        }
+
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_transpositions() {
+        let mut a = Board::default();
+        a.uci("g1f3".to_string(), None).unwrap();
+        a.uci("g8f6".to_string(), None).unwrap();
+        a.uci("b1c3".to_string(), None).unwrap();
+
+        let mut b = Board::default();
+        b.uci("b1c3".to_string(), None).unwrap();
+        b.uci("g8f6".to_string(), None).unwrap();
+        b.uci("g1f3".to_string(), None).unwrap();
+
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), Board::default().hash());
+
+        let mut c = a;
+        c.whites_turn = !c.whites_turn;
+        assert_ne!(a.hash(), c.hash());
+    }
+
+    #[test]
+    fn test_chess960_castling_rights() {
+        // the same placement, with the rights on different rooks
+        let outer = Board::from("1r2k1rr/8/8/8/8/8/8/1R2K1RR w HBhb - 0 1");
+        let inner = Board::from("1r2k1rr/8/8/8/8/8/8/1R2K1RR w GBgb - 0 1");
+        assert!(outer.castling != inner.castling);
+        assert_ne!(outer.hash(), inner.hash());
+    }
+}